@@ -44,6 +44,40 @@ pub async fn get_chart_data(
     Ok(Json(chart_data))
 }
 
+/// Get the percentage of requests within SLA for a time range
+pub async fn get_sla_stats(
+    State(state): State<AppState>,
+    Query(query): Query<ChartQuery>,
+) -> Result<impl IntoResponse, RotaError> {
+    let repo = DashboardRepository::new(state.db.pool().clone());
+
+    let time_range = ChartTimeRange {
+        range: query.range,
+        start: None,
+        end: None,
+    };
+
+    let stats = repo.get_sla_stats(&time_range).await?;
+    Ok(Json(stats))
+}
+
+/// Get how many attempts requests needed for a time range
+pub async fn get_retry_stats(
+    State(state): State<AppState>,
+    Query(query): Query<ChartQuery>,
+) -> Result<impl IntoResponse, RotaError> {
+    let repo = DashboardRepository::new(state.db.pool().clone());
+
+    let time_range = ChartTimeRange {
+        range: query.range,
+        start: None,
+        end: None,
+    };
+
+    let stats = repo.get_retry_stats(&time_range).await?;
+    Ok(Json(stats))
+}
+
 /// Get system metrics
 pub async fn get_system_metrics() -> Result<impl IntoResponse, RotaError> {
     let mut sys = System::new_all();
@@ -81,9 +81,15 @@ pub async fn restore_deleted_proxy(
 
 async fn refresh_selector(state: &AppState) -> Result<(), RotaError> {
     let repo = ProxyRepository::new(state.db.pool().clone());
-    let remove_unhealthy = state.settings_tx.borrow().rotation.remove_unhealthy;
+    let (remove_unhealthy, usable_statuses) = {
+        let settings = state.settings_tx.borrow();
+        (
+            settings.rotation.remove_unhealthy,
+            settings.rotation.usable_status_filter(),
+        )
+    };
     let proxies = if remove_unhealthy {
-        repo.get_all_usable().await?
+        repo.get_all_usable(&usable_statuses).await?
     } else {
         repo.get_all().await?
     };
@@ -1,5 +1,7 @@
 //! Health check endpoint
 
+use std::sync::atomic::Ordering;
+
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
@@ -10,6 +12,8 @@ use sysinfo::System;
 
 use crate::api::server::AppState;
 use crate::error::RotaError;
+use crate::models::ProtocolHealthSummary;
+use crate::repository::ProxyRepository;
 
 /// Health check endpoint
 pub async fn health_check() -> impl IntoResponse {
@@ -27,7 +31,10 @@ struct StatusResponse {
     version: &'static str,
     uptime: u64,
     proxies: ProxyStatusSummary,
+    protocols: Vec<ProtocolHealthSummary>,
     requests: RequestStats,
+    rate_limit: RateLimitStats,
+    log_stream: LogStreamStats,
     system: SystemStats,
 }
 
@@ -46,6 +53,19 @@ struct RequestStats {
     last_hour: i64,
 }
 
+#[derive(Debug, Serialize)]
+struct RateLimitStats {
+    enabled: bool,
+    observe_only: bool,
+    would_block: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct LogStreamStats {
+    /// Records live log subscribers missed because they fell behind
+    dropped: u64,
+}
+
 #[derive(Debug, Serialize)]
 struct SystemStats {
     cpu_usage: f64,
@@ -72,17 +92,26 @@ pub async fn status(State(state): State<AppState>) -> Result<impl IntoResponse,
     .await?;
 
     let last_minute: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*)::bigint FROM proxy_requests WHERE timestamp >= NOW() - INTERVAL '1 minute'",
+        "SELECT COALESCE(ROUND(SUM(sample_weight)), 0)::bigint FROM proxy_requests \
+         WHERE timestamp >= NOW() - INTERVAL '1 minute'",
     )
     .fetch_one(pool)
     .await?;
 
     let last_hour: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*)::bigint FROM proxy_requests WHERE timestamp >= NOW() - INTERVAL '1 hour'",
+        "SELECT COALESCE(ROUND(SUM(sample_weight)), 0)::bigint FROM proxy_requests \
+         WHERE timestamp >= NOW() - INTERVAL '1 hour'",
     )
     .fetch_one(pool)
     .await?;
 
+    let protocols = ProxyRepository::new(pool.clone())
+        .health_by_protocol()
+        .await?
+        .into_iter()
+        .map(ProtocolHealthSummary::from)
+        .collect();
+
     let mut sys = System::new_all();
     sys.refresh_all();
 
@@ -102,11 +131,20 @@ pub async fn status(State(state): State<AppState>) -> Result<impl IntoResponse,
             failed,
             idle,
         },
+        protocols,
         requests: RequestStats {
             total: total_requests,
             last_minute,
             last_hour,
         },
+        rate_limit: RateLimitStats {
+            enabled: state.rate_limiter.is_enabled(),
+            observe_only: state.rate_limiter.is_observe_only(),
+            would_block: state.rate_limiter.would_block_count(),
+        },
+        log_stream: LogStreamStats {
+            dropped: state.log_stream_dropped.load(Ordering::Relaxed),
+        },
         system: SystemStats {
             cpu_usage,
             memory_usage: sys.used_memory(),
@@ -0,0 +1,16 @@
+//! Prometheus metrics endpoint
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+
+use crate::api::server::AppState;
+use crate::proxy::ProxySelector;
+
+/// Proxy traffic counters in the Prometheus text exposition format
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(state.selector.available_count()),
+    )
+}
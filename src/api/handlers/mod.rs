@@ -5,5 +5,9 @@ pub mod dashboard;
 pub mod deleted_proxy;
 pub mod health;
 pub mod logs;
+pub mod metrics;
 pub mod proxy;
+pub mod rate_limit;
+pub mod rotation;
+pub mod services;
 pub mod settings;
@@ -0,0 +1,23 @@
+//! Rate limiter diagnostics handlers
+
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+use crate::api::server::AppState;
+
+/// Query parameters for the throttled clients listing
+#[derive(Debug, Deserialize, Default)]
+pub struct ThrottledClientsQuery {
+    pub limit: Option<usize>,
+}
+
+/// List the clients hitting the rate limit most often
+pub async fn list_throttled_clients(
+    State(state): State<AppState>,
+    Query(query): Query<ThrottledClientsQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(10).clamp(1, 1000);
+    Json(state.rate_limiter.top_throttled(limit))
+}
@@ -0,0 +1,57 @@
+//! Rotation maintenance handlers
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde_json::json;
+use tracing::info;
+
+use crate::api::middleware::AuthenticatedUser;
+use crate::api::server::AppState;
+use crate::error::RotaError;
+use crate::proxy::rotation::ProxySelector;
+use crate::repository::ProxyRepository;
+
+/// Rebuild the selector from scratch with the current settings and proxy pool
+///
+/// Drops connection counts, cursors, circuit breakers and probation along with the old
+/// selector.
+pub async fn rebuild_selector(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, RotaError> {
+    let settings = state.settings_tx.borrow().clone();
+    let repo = ProxyRepository::new(state.db.pool().clone());
+    let proxies = if settings.rotation.remove_unhealthy {
+        repo.get_all_usable(&settings.rotation.usable_status_filter())
+            .await?
+    } else {
+        repo.get_all().await?
+    };
+
+    state.selector.rebuild(&settings.rotation, proxies).await?;
+
+    let strategy = state.selector.strategy_name();
+    let available = state.selector.available_count();
+    info!(strategy, available, "Rebuilt proxy selector");
+
+    Ok(Json(json!({
+        "strategy": strategy,
+        "available_proxies": available,
+    })))
+}
+
+/// Drop the pooled and pre-dialed upstream connections so later requests dial and
+/// handshake afresh
+///
+/// Useful after rotating proxy credentials or when pooled connections look stale.
+pub async fn flush_connections(
+    _admin: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let pooled = state.upstream_pool.clear();
+    let pre_dialed = state.warm_pool.clear();
+    info!(pooled, pre_dialed, "Flushed pooled upstream connections");
+    let flushed = pooled + pre_dialed;
+
+    Json(json!({ "flushed_connections": flushed }))
+}
@@ -0,0 +1,52 @@
+//! Background service control handlers
+
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde_json::json;
+use tracing::info;
+
+use crate::api::server::AppState;
+use crate::error::RotaError;
+
+/// Pause a background service; its loop keeps running but skips its work
+pub async fn pause_service(
+    State(state): State<AppState>,
+    Path(service): Path<String>,
+) -> Result<impl IntoResponse, RotaError> {
+    set_paused(&state, &service, true)
+}
+
+/// Resume a paused background service
+pub async fn resume_service(
+    State(state): State<AppState>,
+    Path(service): Path<String>,
+) -> Result<impl IntoResponse, RotaError> {
+    set_paused(&state, &service, false)
+}
+
+fn set_paused(
+    state: &AppState,
+    service: &str,
+    paused: bool,
+) -> Result<Json<serde_json::Value>, RotaError> {
+    let flag = state.services.get(service).ok_or_else(|| {
+        RotaError::NotFound(format!(
+            "Unknown service '{}'; expected one of {}",
+            service,
+            state.services.names().join(", ")
+        ))
+    })?;
+
+    if paused {
+        flag.pause();
+    } else {
+        flag.resume();
+    }
+    info!(service, paused, "Updated background service state");
+
+    Ok(Json(json!({
+        "service": service,
+        "paused": paused,
+    })))
+}
@@ -11,7 +11,6 @@ use crate::api::server::AppState;
 use crate::error::RotaError;
 use crate::models::Settings;
 use crate::proxy::rotation::ProxySelector;
-use crate::proxy::rotation::RotationStrategy;
 use crate::repository::{ProxyRepository, SettingsRepository};
 
 /// Get all settings
@@ -24,31 +23,82 @@ pub async fn update_settings(
     State(state): State<AppState>,
     Json(settings): Json<Settings>,
 ) -> Result<impl IntoResponse, RotaError> {
+    settings.validate()?;
     let repo = SettingsRepository::new(state.db.pool().clone());
     repo.update_all(&settings).await?;
 
+    apply_settings(&state, &settings).await?;
+
+    info!("Settings updated");
+
+    Ok(Json(settings))
+}
+
+/// Partially update settings
+///
+/// The body is merged over the stored settings, so only the fields being changed
+/// need to be sent.
+pub async fn patch_settings(
+    State(state): State<AppState>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, RotaError> {
+    let repo = SettingsRepository::new(state.db.pool().clone());
+    let mut settings = repo.patch(&patch).await?;
+
+    // Passwords aren't stored with the settings, so carry over the running one.
+    let patches_password = patch
+        .get("authentication")
+        .and_then(|auth| auth.get("password"))
+        .is_some();
+    if !patches_password {
+        settings.authentication.password =
+            state.settings_tx.borrow().authentication.password.clone();
+    }
+
+    apply_settings(&state, &settings).await?;
+
+    info!("Settings patched");
+
+    Ok(Json(settings))
+}
+
+/// Publish new settings and apply them to the running services
+async fn apply_settings(state: &AppState, settings: &Settings) -> Result<(), RotaError> {
     let _ = state.settings_tx.send(settings.clone());
 
-    // Apply rate limiting immediately (proxy server uses the shared instance).
-    state.rate_limiter.apply_settings(&settings.rate_limit);
+    // Rate limits are applied by the shared limiter's settings watcher.
+
+    // Swap the CORS allowlist used by the running API server.
+    state.cors_origins.apply_settings(&settings.cors);
 
     // Refresh proxies & apply rotation strategy immediately.
     let proxy_repo = ProxyRepository::new(state.db.pool().clone());
     let proxies = if settings.rotation.remove_unhealthy {
-        proxy_repo.get_all_usable().await?
+        proxy_repo
+            .get_all_usable(&settings.rotation.usable_status_filter())
+            .await?
     } else {
         proxy_repo.get_all().await?
     };
     state.selector.refresh(proxies).await?;
 
-    let strategy = RotationStrategy::from_str(&settings.rotation.method);
+    // The rotation strategy itself is switched by the selector's settings watcher.
     let interval_secs = settings.rotation.time_based.interval.max(1) as u64;
     state
         .selector
-        .set_strategy(strategy, Duration::from_secs(interval_secs))
+        .set_host_rules(
+            &settings.rotation.host_rules,
+            Duration::from_secs(interval_secs),
+        )
         .await?;
+    state.selector.set_recovery_gate(
+        settings.rotation.recovery_min_success_rate,
+        settings.rotation.recovery_window.max(1) as usize,
+    );
+    state.selector.set_circuit_breaker(
+        settings.rotation.circuit_breaker_failures.max(0) as u32,
+        Duration::from_secs(settings.rotation.circuit_breaker_cooldown.max(1) as u64),
+    );
 
-    info!("Settings updated");
-
-    Ok(Json(settings))
+    Ok(())
 }
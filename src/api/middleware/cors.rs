@@ -2,54 +2,94 @@
 //!
 //! Fixed: Uses explicit domain whitelist instead of allowing all origins.
 
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
 use axum::http::header;
+use axum::http::request::Parts;
 use axum::http::{HeaderValue, Method};
-use tower_http::cors::CorsLayer;
-use tracing::debug;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::{debug, info};
+
+use crate::models::CorsSettings;
 
-/// Create a CORS layer with the specified allowed origins
+/// Allowed CORS origins, swappable at runtime
+///
+/// Starts from the `CORS_ORIGINS` environment list (localhost when empty).
+/// Origins set via settings replace that list until cleared again.
+#[derive(Clone)]
+pub struct CorsOrigins {
+    defaults: Arc<Vec<HeaderValue>>,
+    current: Arc<ArcSwap<Vec<HeaderValue>>>,
+}
+
+impl CorsOrigins {
+    pub fn new(configured: &[String]) -> Self {
+        let defaults = if configured.is_empty() {
+            debug!("CORS: No origins specified, allowing localhost only");
+            vec![
+                HeaderValue::from_static("http://localhost:3000"),
+                HeaderValue::from_static("http://127.0.0.1:3000"),
+            ]
+        } else {
+            debug!("CORS: Allowing origins: {:?}", configured);
+            parse_origins(configured)
+        };
+
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(defaults.clone())),
+            defaults: Arc::new(defaults),
+        }
+    }
+
+    /// Replace the allowlist; an empty list restores the startup defaults
+    pub fn set(&self, origins: &[String]) {
+        if origins.is_empty() {
+            self.current.store(self.defaults.clone());
+        } else {
+            info!("CORS: Allowing origins: {:?}", origins);
+            self.current.store(Arc::new(parse_origins(origins)));
+        }
+    }
+
+    /// Apply the CORS block from runtime settings
+    pub fn apply_settings(&self, settings: &CorsSettings) {
+        self.set(&settings.allowed_origins);
+    }
+
+    /// Whether `origin` is currently allowed
+    pub fn is_allowed(&self, origin: &HeaderValue) -> bool {
+        self.current.load().iter().any(|o| o == origin)
+    }
+}
+
+fn parse_origins(origins: &[String]) -> Vec<HeaderValue> {
+    origins
+        .iter()
+        .filter_map(|o| o.trim().parse().ok())
+        .collect()
+}
+
+/// Create a CORS layer that checks `origins` on every request
 ///
 /// This fixes the security issue from the Go implementation where
 /// CORS was allowing all origins with credentials.
-pub fn cors_layer(allowed_origins: &[String]) -> CorsLayer {
+pub fn cors_layer(origins: CorsOrigins) -> CorsLayer {
     let allowed_headers = [header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT];
 
-    if allowed_origins.is_empty() {
-        debug!("CORS: No origins specified, allowing localhost only");
-        // Default to localhost only
-        CorsLayer::new()
-            .allow_origin([
-                "http://localhost:3000".parse::<HeaderValue>().unwrap(),
-                "http://127.0.0.1:3000".parse::<HeaderValue>().unwrap(),
-            ])
-            .allow_methods([
-                Method::GET,
-                Method::POST,
-                Method::PUT,
-                Method::DELETE,
-                Method::OPTIONS,
-            ])
-            .allow_headers(allowed_headers)
-            .allow_credentials(true)
-    } else {
-        debug!("CORS: Allowing origins: {:?}", allowed_origins);
-        let origins: Vec<HeaderValue> = allowed_origins
-            .iter()
-            .filter_map(|o| o.parse().ok())
-            .collect();
-
-        CorsLayer::new()
-            .allow_origin(origins)
-            .allow_methods([
-                Method::GET,
-                Method::POST,
-                Method::PUT,
-                Method::DELETE,
-                Method::OPTIONS,
-            ])
-            .allow_headers(allowed_headers)
-            .allow_credentials(true)
-    }
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(
+            move |origin: &HeaderValue, _parts: &Parts| origins.is_allowed(origin),
+        ))
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers(allowed_headers)
+        .allow_credentials(true)
 }
 
 #[cfg(test)]
@@ -64,7 +104,7 @@ mod tests {
     async fn test_cors_empty_origins_allows_localhost() {
         let app = axum::Router::new()
             .route("/", get(|| async { "ok" }))
-            .layer(cors_layer(&[]));
+            .layer(cors_layer(CorsOrigins::new(&[])));
 
         let response = app
             .oneshot(
@@ -93,7 +133,7 @@ mod tests {
     async fn test_cors_empty_origins_blocks_other_origins() {
         let app = axum::Router::new()
             .route("/", get(|| async { "ok" }))
-            .layer(cors_layer(&[]));
+            .layer(cors_layer(CorsOrigins::new(&[])));
 
         let response = app
             .oneshot(
@@ -122,7 +162,7 @@ mod tests {
 
         let app = axum::Router::new()
             .route("/", get(|| async { "ok" }))
-            .layer(cors_layer(&origins));
+            .layer(cors_layer(CorsOrigins::new(&origins)));
 
         let response = app
             .oneshot(
@@ -146,4 +186,50 @@ mod tests {
             "https://app.example.com"
         );
     }
+
+    #[tokio::test]
+    async fn test_cors_origin_added_at_runtime_is_accepted() {
+        let origins = CorsOrigins::new(&[]);
+        let app = axum::Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(cors_layer(origins.clone()));
+
+        let request = || {
+            Request::builder()
+                .method(Method::GET)
+                .uri("/")
+                .header("Origin", "https://new.example.com")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert!(response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none());
+
+        origins.apply_settings(&CorsSettings {
+            allowed_origins: vec!["https://new.example.com".to_string()],
+        });
+
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(
+            response
+                .headers()
+                .get("access-control-allow-origin")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "https://new.example.com"
+        );
+
+        // Clearing the settings list restores the startup defaults
+        origins.apply_settings(&CorsSettings::default());
+        let response = app.oneshot(request()).await.unwrap();
+        assert!(response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none());
+    }
 }
@@ -4,6 +4,6 @@ mod cors;
 mod jwt;
 mod logging;
 
-pub use cors::cors_layer;
+pub use cors::{cors_layer, CorsOrigins};
 pub use jwt::{AuthError, AuthenticatedUser, Claims, JwtAuth};
 pub use logging::RequestLogging;
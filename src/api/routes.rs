@@ -1,7 +1,7 @@
 //! API route definitions
 
-use axum::routing::{delete, get, post, put};
-use axum::Router;
+use axum::routing::{delete, get, patch, post, put};
+use axum::{Extension, Router};
 
 use super::handlers;
 use super::server::AppState;
@@ -12,6 +12,7 @@ pub fn create_router(state: AppState) -> Router {
     Router::new()
         // Health check (no auth required)
         .route("/health", get(handlers::health::health_check))
+        .route("/metrics", get(handlers::metrics::metrics))
         .route("/api/status", get(handlers::health::status))
         // Temporary compatibility: forward /api/v1/* to /api/*
         .route("/api/v1/status", get(handlers::health::status))
@@ -23,6 +24,8 @@ pub fn create_router(state: AppState) -> Router {
         .nest("/api", protected_routes())
         // Temporary compatibility: forward /api/v1/* to /api/*
         .nest("/api/v1", protected_routes())
+        // Lets handlers require a signed-in admin through `AuthenticatedUser`
+        .layer(Extension(state.jwt_auth.clone()))
         .with_state(state)
 }
 
@@ -33,10 +36,26 @@ fn protected_routes() -> Router<AppState> {
         .route("/proxies", get(handlers::proxy::list_proxies))
         .route("/proxies", post(handlers::proxy::create_proxy))
         .route("/proxies/bulk", post(handlers::proxy::bulk_create_proxies))
+        .route("/proxies/export", get(handlers::proxy::export_proxies))
+        .route("/proxies/import", post(handlers::proxy::import_proxies))
+        .route("/proxies/tags", post(handlers::proxy::bulk_tag_proxies))
+        .route(
+            "/proxies/tags/:tag",
+            delete(handlers::proxy::delete_proxies_by_tag),
+        )
+        .route(
+            "/proxies/tags/:tag/enabled",
+            put(handlers::proxy::set_tag_enabled),
+        )
         .route("/proxies/:id", get(handlers::proxy::get_proxy))
         .route("/proxies/:id", put(handlers::proxy::update_proxy))
         .route("/proxies/:id", delete(handlers::proxy::delete_proxy))
         .route("/proxies/:id/toggle", post(handlers::proxy::toggle_proxy))
+        .route(
+            "/proxies/:id/eligibility",
+            get(handlers::proxy::get_proxy_eligibility),
+        )
+        .route("/proxies/:id/check", post(handlers::proxy::check_proxy))
         // Deleted proxies archive
         .route(
             "/deleted_proxies",
@@ -50,15 +69,44 @@ fn protected_routes() -> Router<AppState> {
             "/deleted_proxies/:id/restore",
             post(handlers::deleted_proxy::restore_deleted_proxy),
         )
+        // Rotation
+        .route(
+            "/rotation/rebuild",
+            post(handlers::rotation::rebuild_selector),
+        )
+        .route(
+            "/rotation/flush-connections",
+            post(handlers::rotation::flush_connections),
+        )
+        // Background services
+        .route(
+            "/services/:service/pause",
+            post(handlers::services::pause_service),
+        )
+        .route(
+            "/services/:service/resume",
+            post(handlers::services::resume_service),
+        )
         // Settings
         .route("/settings", get(handlers::settings::get_settings))
         .route("/settings", put(handlers::settings::update_settings))
+        .route("/settings", patch(handlers::settings::patch_settings))
+        // Rate limiting
+        .route(
+            "/rate-limit/clients",
+            get(handlers::rate_limit::list_throttled_clients),
+        )
         // Logs
         .route("/logs", get(handlers::logs::list_logs))
         .route("/logs/export", get(handlers::logs::export_logs))
         // Dashboard
         .route("/dashboard/stats", get(handlers::dashboard::get_stats))
         .route("/dashboard/chart", get(handlers::dashboard::get_chart_data))
+        .route("/dashboard/sla", get(handlers::dashboard::get_sla_stats))
+        .route(
+            "/dashboard/retries",
+            get(handlers::dashboard::get_retry_stats),
+        )
         .route(
             "/dashboard/system",
             get(handlers::dashboard::get_system_metrics),
@@ -82,12 +130,16 @@ mod tests {
     use tower::ServiceExt;
 
     use crate::config::{
-        AdminConfig, ApiServerConfig, Config, DatabaseConfig, LogConfig, ProxyServerConfig,
+        AdminConfig, ApiServerConfig, Config, DatabaseConfig, DenylistConfig, Limits, LogConfig,
+        ProxyServerConfig, StartupHealthcheck, WarmPoolConfig,
     };
     use crate::database::Database;
     use crate::models::{RequestRecord, Settings};
     use crate::proxy::middleware::RateLimiter;
     use crate::proxy::rotation::{create_selector, DynamicProxySelector, RotationStrategy};
+    use crate::proxy::upstream_pool::UpstreamPool;
+    use crate::proxy::warm_pool::WarmPool;
+    use crate::services::{PauseFlag, ServiceControls};
 
     fn test_state() -> AppState {
         let pool = PgPoolOptions::new()
@@ -100,22 +152,54 @@ mod tests {
                 port: 8000,
                 host: "127.0.0.1".to_string(),
                 max_retries: 3,
-                connect_timeout: 10,
+                connect_timeout_http: 10,
+                connect_timeout_https: 10,
+                connect_timeout_socks4: 10,
+                connect_timeout_socks5: 10,
                 request_timeout: 30,
                 auth_enabled: false,
                 auth_username: "".to_string(),
                 auth_password: "".to_string(),
+                auth_realm: "Proxy".to_string(),
                 rate_limit_enabled: false,
                 rate_limit_per_second: 100,
                 rate_limit_burst: 200,
                 rotation_strategy: "random".to_string(),
                 egress_proxy: None,
+                egress_proxies: Vec::new(),
+                connect_excluded_protocols: vec![],
+                multi_value_headers: "preserve".to_string(),
+                sla_threshold_ms: 2000,
+                body_read_timeout: 30,
+                canary_requests: false,
+                cap_retries_to_pool: true,
+                max_concurrent_requests: 0,
+                max_tunnels: 0,
+                success_log_sample_rate: 1.0,
+                limits: Limits::default(),
+                denylist: DenylistConfig::default(),
+                trusted_proxy_cidrs: vec![],
+                client_ip_header: hyper::header::HeaderName::from_static("x-forwarded-for"),
+                expose_error_details: false,
+                warm_pool: WarmPoolConfig::default(),
+                pool_max_idle_per_proxy: 8,
+                retry_backoff_ms: 100,
+                retry_backoff_max_ms: 2000,
+                client_idle_timeout: 60,
+                shutdown_grace: 30,
+                startup_delay: 0,
+                wait_for_health_check: false,
+                healthcheck_start_jitter: 0,
+                healthcheck_jitter_seed: None,
+                startup_healthcheck: StartupHealthcheck::Off,
+                geoip_databases: Vec::new(),
             },
             api: ApiServerConfig {
                 port: 8001,
                 host: "127.0.0.1".to_string(),
                 cors_origins: Vec::new(),
                 jwt_secret: "test-secret".to_string(),
+                default_proxy_protocol: "http".to_string(),
             },
             database: DatabaseConfig {
                 host: "localhost".to_string(),
@@ -126,6 +210,8 @@ mod tests {
                 ssl_mode: "disable".to_string(),
                 max_connections: 1,
                 min_connections: 0,
+                connect_retry_timeout: 0,
+                fail_open: false,
             },
             admin: AdminConfig {
                 username: "admin".to_string(),
@@ -134,6 +220,7 @@ mod tests {
             log: LogConfig {
                 level: "info".to_string(),
                 format: "json".to_string(),
+                stream_buffer: 1024,
             },
         };
 
@@ -150,8 +237,15 @@ mod tests {
             started_at: Instant::now(),
             selector,
             log_sender,
+            log_stream_dropped: Default::default(),
+            status_events: broadcast::channel(1).0,
             settings_tx,
             rate_limiter: RateLimiter::disabled(),
+            cors_origins: crate::api::middleware::CorsOrigins::new(&config.api.cors_origins),
+            metrics: Default::default(),
+            services: ServiceControls::new(vec![PauseFlag::new("health")]),
+            upstream_pool: UpstreamPool::new(8),
+            warm_pool: WarmPool::disabled(),
         }
     }
 
@@ -180,6 +274,179 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
+    #[tokio::test]
+    async fn test_rate_limit_clients_route_lists_throttled_clients() {
+        let state = test_state();
+        state
+            .rate_limiter
+            .apply_settings(&crate::models::RateLimitSettings {
+                enabled: true,
+                interval: 60,
+                max_requests: 1,
+                observe_only: false,
+                ..Default::default()
+            });
+        state.rate_limiter.check("10.0.0.1").ok();
+        state.rate_limiter.check("10.0.0.1").ok();
+
+        let app = create_router(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/api/rate-limit/clients?limit=5")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let clients: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            clients,
+            json!([{ "client": "10.0.0.1", "blocked": 1, "would_block": 0 }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_services_routes_pause_and_resume() {
+        let state = test_state();
+        let health = state.services.get("health").unwrap().clone();
+
+        async fn send(state: AppState, uri: &str) -> StatusCode {
+            create_router(state)
+                .oneshot(
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+        }
+
+        assert_eq!(
+            send(state.clone(), "/api/services/health/pause").await,
+            StatusCode::OK
+        );
+        assert!(health.is_paused());
+        assert_eq!(
+            send(state.clone(), "/api/services/health/resume").await,
+            StatusCode::OK
+        );
+        assert!(!health.is_paused());
+        assert_eq!(
+            send(state, "/api/services/nope/pause").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flush_connections_requires_admin_token() {
+        let state = test_state();
+        let token = state.jwt_auth.generate_token("admin", 1).unwrap();
+
+        async fn send(state: AppState, token: Option<&str>) -> StatusCode {
+            let mut request = Request::builder()
+                .method(Method::POST)
+                .uri("/api/rotation/flush-connections");
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            create_router(state)
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        }
+
+        assert_eq!(send(state.clone(), None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            send(state.clone(), Some("not-a-token")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(send(state, Some(&token)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_export_credentials_require_admin_token() {
+        let state = test_state();
+        let token = state.jwt_auth.generate_token("admin", 1).unwrap();
+
+        async fn send(state: AppState, uri: &str, token: Option<&str>) -> StatusCode {
+            let mut request = Request::builder().uri(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            create_router(state)
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        }
+
+        let with_credentials = "/api/proxies/export?format=txt&include_credentials=true";
+        assert_eq!(
+            send(state.clone(), with_credentials, None).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(state.clone(), with_credentials, Some("not-a-token")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(state.clone(), with_credentials, Some(&token)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            send(state, "/api/proxies/export?format=txt", None).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_route_exports_prometheus_text() {
+        let state = test_state();
+        let metrics = state.metrics.clone();
+
+        async fn scrape(state: AppState) -> String {
+            let response = create_router(state)
+                .oneshot(
+                    Request::builder()
+                        .method(Method::GET)
+                        .uri("/metrics")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        }
+
+        let text = scrape(state.clone()).await;
+        assert!(text.contains("# HELP rota_requests_total "));
+        assert!(text.contains("# TYPE rota_requests_total counter"));
+        assert!(text.contains("# TYPE rota_active_tunnels gauge"));
+        assert!(text.contains("rota_requests_total 0\n"));
+
+        // A request served by the proxy handler
+        metrics.record_response(hyper::StatusCode::OK);
+
+        let text = scrape(state).await;
+        assert!(text.contains("rota_requests_total 1\n"));
+        assert!(text.contains("rota_responses_total{code=\"200\"} 1\n"));
+    }
+
     #[tokio::test]
     async fn test_api_v1_ws_route_is_registered() {
         let app = create_router(test_state());
@@ -197,4 +464,23 @@ mod tests {
 
         assert_ne!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_settings_patch_rejects_unknown_section() {
+        let app = create_router(test_state());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::PATCH)
+                    .uri("/api/settings")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(json!({ "nope": {} }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -3,6 +3,7 @@
 //! Provides REST API endpoints for managing proxies, settings, and viewing logs.
 
 use std::net::SocketAddr;
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -14,11 +15,15 @@ use tracing::{info, instrument};
 use crate::config::{ApiServerConfig, Config};
 use crate::database::Database;
 use crate::error::Result;
-use crate::models::{RequestRecord, Settings};
+use crate::models::{ProxyStatusEvent, RequestRecord, Settings};
+use crate::proxy::metrics::ProxyMetrics;
 use crate::proxy::middleware::RateLimiter;
 use crate::proxy::rotation::DynamicProxySelector;
+use crate::proxy::upstream_pool::UpstreamPool;
+use crate::proxy::warm_pool::WarmPool;
+use crate::services::ServiceControls;
 
-use super::middleware::{cors_layer, JwtAuth};
+use super::middleware::{cors_layer, CorsOrigins, JwtAuth};
 use super::routes;
 
 /// Shared state for API handlers
@@ -30,8 +35,21 @@ pub struct AppState {
     pub started_at: Instant,
     pub selector: Arc<DynamicProxySelector>,
     pub log_sender: broadcast::Sender<RequestRecord>,
+    /// Log records live log subscribers have missed because they fell behind
+    pub log_stream_dropped: Arc<AtomicU64>,
+    /// Proxy status changes pushed to dashboard clients
+    pub status_events: broadcast::Sender<ProxyStatusEvent>,
     pub settings_tx: watch::Sender<Settings>,
     pub rate_limiter: RateLimiter,
+    pub cors_origins: CorsOrigins,
+    /// Proxy traffic counters served on `/metrics`
+    pub metrics: Arc<ProxyMetrics>,
+    /// Background services that can be paused and resumed
+    pub services: ServiceControls,
+    /// Idle upstream connections of the proxy server, flushed on request
+    pub upstream_pool: UpstreamPool,
+    /// Pre-dialed proxy connections of the proxy server, flushed on request
+    pub warm_pool: WarmPool,
 }
 
 /// API server
@@ -52,6 +70,8 @@ impl ApiServer {
         rate_limiter: RateLimiter,
     ) -> Self {
         let jwt_auth = JwtAuth::new(&api_config.jwt_secret);
+        let cors_origins = CorsOrigins::new(&api_config.cors_origins);
+        cors_origins.apply_settings(&settings_tx.borrow().cors);
 
         let state = AppState {
             db,
@@ -60,8 +80,15 @@ impl ApiServer {
             started_at: Instant::now(),
             selector,
             log_sender,
+            log_stream_dropped: Arc::new(AtomicU64::new(0)),
+            status_events: broadcast::channel(1).0,
             settings_tx,
             rate_limiter,
+            cors_origins,
+            metrics: Arc::new(ProxyMetrics::new()),
+            services: ServiceControls::default(),
+            upstream_pool: UpstreamPool::new(0),
+            warm_pool: WarmPool::disabled(),
         };
 
         Self {
@@ -70,9 +97,39 @@ impl ApiServer {
         }
     }
 
+    /// Serve the proxy server's counters on `/metrics`
+    pub fn with_metrics(mut self, metrics: Arc<ProxyMetrics>) -> Self {
+        self.state.metrics = metrics;
+        self
+    }
+
+    /// Push status changes sent on `sender` to dashboard clients
+    pub fn with_status_events(mut self, sender: broadcast::Sender<ProxyStatusEvent>) -> Self {
+        self.state.status_events = sender;
+        self
+    }
+
+    /// Let the API pause and resume the services in `controls`
+    pub fn with_service_controls(mut self, controls: ServiceControls) -> Self {
+        self.state.services = controls;
+        self
+    }
+
+    /// Let the API flush the proxy server's idle upstream connections
+    pub fn with_upstream_pool(mut self, pool: UpstreamPool) -> Self {
+        self.state.upstream_pool = pool;
+        self
+    }
+
+    /// Let the API flush the proxy server's pre-dialed connections
+    pub fn with_warm_pool(mut self, pool: WarmPool) -> Self {
+        self.state.warm_pool = pool;
+        self
+    }
+
     /// Build the router
     fn build_router(&self) -> Router {
-        let cors = cors_layer(&self.config.cors_origins);
+        let cors = cors_layer(self.state.cors_origins.clone());
 
         routes::create_router(self.state.clone())
             .layer(cors)
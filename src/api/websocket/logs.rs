@@ -3,63 +3,135 @@
 //! Provides real-time log streaming.
 //! FIXED: Uses bounded channels with try_send to prevent memory leaks.
 
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::response::IntoResponse;
 use futures::{SinkExt, StreamExt};
-use tokio::sync::mpsc;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, info, warn};
 
 use super::WS_BUFFER_SIZE;
 use crate::api::server::AppState;
 use crate::models::RequestRecord;
+use crate::proxy::metrics::ProxyMetrics;
+
+/// Query parameters for the log stream
+#[derive(Debug, Default, Deserialize)]
+pub struct LogsWsQuery {
+    /// Send a `{"type":"missed","count":N}` message when records were dropped
+    #[serde(default)]
+    pub notify_missed: bool,
+}
+
+/// Item queued for a log stream client
+#[derive(Debug)]
+enum LogStreamEvent {
+    Record(Box<RequestRecord>),
+    /// Number of records the client missed since the last notice
+    Missed(u64),
+}
+
+impl LogStreamEvent {
+    fn to_json(&self) -> serde_json::Result<String> {
+        match self {
+            LogStreamEvent::Record(record) => serde_json::to_string(record),
+            LogStreamEvent::Missed(count) => {
+                serde_json::to_string(&json!({ "type": "missed", "count": count }))
+            }
+        }
+    }
+}
 
 /// WebSocket handler for log streaming
-pub async fn logs_ws(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_logs_ws(socket, state))
+pub async fn logs_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<LogsWsQuery>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_logs_ws(socket, state, query))
+}
+
+/// Forward broadcast log records to a client's queue until either side closes
+///
+/// Records skipped because the subscriber lagged behind the broadcast channel, or
+/// because the client's queue was full, are added to `dropped`; lagging is also counted
+/// in `metrics`. With `notify_missed` the client is told how many records it missed
+/// before the next record it gets.
+async fn forward_records(
+    mut log_rx: broadcast::Receiver<RequestRecord>,
+    tx: mpsc::Sender<LogStreamEvent>,
+    dropped: Arc<AtomicU64>,
+    metrics: Arc<ProxyMetrics>,
+    notify_missed: bool,
+) {
+    let mut missed = 0u64;
+
+    loop {
+        match log_rx.recv().await {
+            Ok(record) => {
+                if notify_missed && missed > 0 {
+                    match tx.try_send(LogStreamEvent::Missed(missed)) {
+                        Ok(()) => missed = 0,
+                        Err(mpsc::error::TrySendError::Full(_)) => {}
+                        Err(mpsc::error::TrySendError::Closed(_)) => break,
+                    }
+                }
+
+                // Use try_send to avoid blocking - fixes memory leak from Go
+                match tx.try_send(LogStreamEvent::Record(Box::new(record))) {
+                    Ok(()) => {}
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        debug!("Logs WebSocket buffer full, dropping log entry");
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                        missed += 1;
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => {
+                        break;
+                    }
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("Logs WebSocket lagged, missed {} messages", n);
+                dropped.fetch_add(n, Ordering::Relaxed);
+                metrics.record_dropped_broadcasts(n);
+                missed += n;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                debug!("Log broadcast channel closed");
+                break;
+            }
+        }
+    }
 }
 
 /// Handle WebSocket connection for logs
-async fn handle_logs_ws(socket: WebSocket, state: AppState) {
+async fn handle_logs_ws(socket: WebSocket, state: AppState, query: LogsWsQuery) {
     let (mut sender, mut receiver) = socket.split();
-    let (tx, mut rx) = mpsc::channel::<RequestRecord>(WS_BUFFER_SIZE);
+    let (tx, mut rx) = mpsc::channel::<LogStreamEvent>(WS_BUFFER_SIZE);
 
     info!("Logs WebSocket connected");
 
     // Subscribe to log broadcasts
-    let mut log_rx = state.log_sender.subscribe();
+    let log_rx = state.log_sender.subscribe();
 
     // Spawn task to receive broadcasts and forward to channel
-    let mut forward_task = tokio::spawn(async move {
-        loop {
-            match log_rx.recv().await {
-                Ok(record) => {
-                    // Use try_send to avoid blocking - fixes memory leak from Go
-                    match tx.try_send(record) {
-                        Ok(()) => {}
-                        Err(mpsc::error::TrySendError::Full(_)) => {
-                            debug!("Logs WebSocket buffer full, dropping log entry");
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            break;
-                        }
-                    }
-                }
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                    warn!("Logs WebSocket lagged, missed {} messages", n);
-                }
-                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
-                    debug!("Log broadcast channel closed");
-                    break;
-                }
-            }
-        }
-    });
+    let mut forward_task = tokio::spawn(forward_records(
+        log_rx,
+        tx,
+        state.log_stream_dropped.clone(),
+        state.metrics.clone(),
+        query.notify_missed,
+    ));
 
     // Spawn task to send logs to WebSocket
     let mut send_task = tokio::spawn(async move {
-        while let Some(record) = rx.recv().await {
-            match serde_json::to_string(&record) {
+        while let Some(event) = rx.recv().await {
+            match event.to_json() {
                 Ok(json) => {
                     if sender.send(Message::Text(json)).await.is_err() {
                         break;
@@ -107,3 +179,53 @@ async fn handle_logs_ws(socket: WebSocket, state: AppState) {
 
     info!("Logs WebSocket disconnected");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_record(proxy_id: i32) -> RequestRecord {
+        RequestRecord {
+            proxy_id,
+            proxy_address: "127.0.0.1:8080".to_string(),
+            requested_url: "http://example.com/".to_string(),
+            method: "GET".to_string(),
+            success: true,
+            response_time: 10,
+            gateway_latency: 10,
+            status_code: 200,
+            error_message: None,
+            timestamp: chrono::Utc::now(),
+            within_sla: true,
+            attempts: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_reports_missed_records() {
+        let (log_tx, log_rx) = broadcast::channel::<RequestRecord>(2);
+        for id in 1..=5 {
+            log_tx.send(test_record(id)).unwrap();
+        }
+        drop(log_tx);
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let metrics = Arc::new(ProxyMetrics::new());
+        forward_records(log_rx, tx, dropped.clone(), metrics.clone(), true).await;
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event.to_json().unwrap());
+        }
+
+        assert_eq!(dropped.load(Ordering::Relaxed), 3);
+        assert_eq!(metrics.dropped_broadcasts(), 3);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], r#"{"count":3,"type":"missed"}"#);
+        for (event, id) in events[1..].iter().zip([4, 5]) {
+            let record: RequestRecord = serde_json::from_str(event).unwrap();
+            assert_eq!(record.proxy_id, id);
+        }
+    }
+}
@@ -89,6 +89,43 @@ fn get_migrations() -> Vec<(i32, &'static str, &'static str)> {
             MIGRATION_005_DROP_UNIQUE_PROXY_ADDRESS,
         ),
         (6, "deleted_proxies", MIGRATION_006_DELETED_PROXIES),
+        (7, "proxy_requests_sla", MIGRATION_007_PROXY_REQUESTS_SLA),
+        (8, "proxy_enabled_flag", MIGRATION_008_PROXY_ENABLED),
+        (9, "proxy_geolocation", MIGRATION_009_PROXY_GEOLOCATION),
+        (10, "proxy_tags", MIGRATION_010_PROXY_TAGS),
+        (11, "proxy_verified_at", MIGRATION_011_PROXY_VERIFIED_AT),
+        (
+            12,
+            "proxy_requests_attempts",
+            MIGRATION_012_PROXY_REQUESTS_ATTEMPTS,
+        ),
+        (
+            13,
+            "proxy_health_check_url",
+            MIGRATION_013_PROXY_HEALTH_CHECK_URL,
+        ),
+        (
+            14,
+            "proxy_expected_response",
+            MIGRATION_014_PROXY_EXPECTED_RESPONSE,
+        ),
+        (
+            15,
+            "proxy_requests_gateway_latency",
+            MIGRATION_015_PROXY_REQUESTS_GATEWAY_LATENCY,
+        ),
+        (16, "proxy_tunnel_bytes", MIGRATION_016_PROXY_TUNNEL_BYTES),
+        (
+            17,
+            "deleted_proxy_tunnel_bytes",
+            MIGRATION_017_DELETED_PROXY_TUNNEL_BYTES,
+        ),
+        (
+            18,
+            "proxy_requests_sample_weight",
+            MIGRATION_018_PROXY_REQUESTS_SAMPLE_WEIGHT,
+        ),
+        (19, "proxy_geolocated_at", MIGRATION_019_PROXY_GEOLOCATED_AT),
     ]
 }
 
@@ -268,3 +305,118 @@ CREATE TABLE IF NOT EXISTS deleted_proxies (
 CREATE INDEX IF NOT EXISTS idx_proxies_invalid_since ON proxies(invalid_since);
 CREATE INDEX IF NOT EXISTS idx_deleted_proxies_deleted_at ON deleted_proxies(deleted_at DESC);
 "#;
+
+// Migration 7: SLA tag on proxy requests (NULL for rows recorded before tagging existed)
+const MIGRATION_007_PROXY_REQUESTS_SLA: &str = r#"
+ALTER TABLE proxy_requests ADD COLUMN IF NOT EXISTS within_sla BOOLEAN;
+"#;
+
+// Migration 8: Operator-controlled enabled flag, separate from health status
+const MIGRATION_008_PROXY_ENABLED: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS enabled BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE INDEX IF NOT EXISTS idx_proxies_enabled ON proxies(enabled);
+"#;
+
+// Migration 9: Country/ASN from the optional GeoIP database
+const MIGRATION_009_PROXY_GEOLOCATION: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS country VARCHAR(2);
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS asn BIGINT;
+
+CREATE INDEX IF NOT EXISTS idx_proxies_country ON proxies(country);
+CREATE INDEX IF NOT EXISTS idx_proxies_asn ON proxies(asn);
+"#;
+
+// Migration 10: Operator-assigned tags for grouping proxies
+const MIGRATION_010_PROXY_TAGS: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_proxies_tags ON proxies USING GIN (tags);
+"#;
+
+// Migration 11: Time of the last passing health check
+const MIGRATION_011_PROXY_VERIFIED_AT: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS verified_at TIMESTAMPTZ;
+"#;
+
+// Migration 12: Attempt number of each proxied request (NULL for rows recorded before)
+const MIGRATION_012_PROXY_REQUESTS_ATTEMPTS: &str = r#"
+ALTER TABLE proxy_requests ADD COLUMN IF NOT EXISTS attempts INTEGER;
+"#;
+
+// Migration 13: Per-proxy health check URL overriding the global one
+const MIGRATION_013_PROXY_HEALTH_CHECK_URL: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS health_check_url TEXT;
+"#;
+
+// Migration 14: Text a proxy's health check response must contain
+const MIGRATION_014_PROXY_EXPECTED_RESPONSE: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS expected_response_contains TEXT;
+"#;
+
+// Migration 15: Total gateway latency of each proxied request (NULL for rows recorded before)
+const MIGRATION_015_PROXY_REQUESTS_GATEWAY_LATENCY: &str = r#"
+ALTER TABLE proxy_requests ADD COLUMN IF NOT EXISTS gateway_latency INTEGER;
+"#;
+
+// Migration 16: Bytes relayed through each proxy's CONNECT tunnels
+const MIGRATION_016_PROXY_TUNNEL_BYTES: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS bytes_sent BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS bytes_received BIGINT NOT NULL DEFAULT 0;
+"#;
+
+// Migration 17: Tunnel byte totals kept when a proxy is archived
+const MIGRATION_017_DELETED_PROXY_TUNNEL_BYTES: &str = r#"
+ALTER TABLE deleted_proxies ADD COLUMN IF NOT EXISTS bytes_sent BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE deleted_proxies ADD COLUMN IF NOT EXISTS bytes_received BIGINT NOT NULL DEFAULT 0;
+"#;
+
+// Migration 18: How many requests each logged request stands for once successes are sampled
+const MIGRATION_018_PROXY_REQUESTS_SAMPLE_WEIGHT: &str = r#"
+ALTER TABLE proxy_requests ADD COLUMN IF NOT EXISTS sample_weight DOUBLE PRECISION NOT NULL DEFAULT 1;
+"#;
+
+// Migration 19: When a proxy's address was last looked up, whether or not it resolved
+const MIGRATION_019_PROXY_GEOLOCATED_AT: &str = r#"
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS geolocated_at TIMESTAMPTZ;
+
+UPDATE proxies SET geolocated_at = updated_at
+WHERE geolocated_at IS NULL AND (country IS NOT NULL OR asn IS NOT NULL);
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (index, (version, _, _)) in get_migrations().iter().enumerate() {
+            assert_eq!(*version, index as i32 + 1);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires ROTA_TEST_DATABASE_URL"]
+    async fn test_byte_accounting_columns_are_applied() {
+        let pool = crate::database::test_pool().await;
+        // Applied migrations are skipped on a second run
+        run_migrations(&pool).await.unwrap();
+
+        assert!(is_migration_applied(&pool, 16).await.unwrap());
+        assert!(is_migration_applied(&pool, 17).await.unwrap());
+        for table in ["proxies", "deleted_proxies"] {
+            let columns: Vec<String> = sqlx::query_scalar(
+                r#"
+                SELECT column_name::text FROM information_schema.columns
+                WHERE table_name = $1 AND column_name IN ('bytes_sent', 'bytes_received')
+                ORDER BY column_name
+                "#,
+            )
+            .bind(table)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            assert_eq!(columns, vec!["bytes_received", "bytes_sent"], "{}", table);
+        }
+    }
+}
@@ -3,3 +3,18 @@ pub mod pool;
 pub mod timescale;
 
 pub use pool::*;
+
+/// Pool for the database in `ROTA_TEST_DATABASE_URL` with every migration applied, for
+/// tests marked `#[ignore = "requires ROTA_TEST_DATABASE_URL"]`
+#[cfg(test)]
+pub(crate) async fn test_pool() -> sqlx::PgPool {
+    let url = std::env::var("ROTA_TEST_DATABASE_URL")
+        .expect("ROTA_TEST_DATABASE_URL must point at a disposable test database");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&url)
+        .await
+        .unwrap();
+    migrations::run_migrations(&pool).await.unwrap();
+    pool
+}
@@ -1,8 +1,14 @@
 use crate::config::Config;
 use crate::error::{Result, RotaError};
 use sqlx::postgres::{PgPool, PgPoolOptions};
-use std::time::Duration;
-use tracing::info;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// First delay between database connection attempts
+const CONNECT_RETRY_BASE: Duration = Duration::from_millis(500);
+
+/// Upper bound for the delay between database connection attempts
+const CONNECT_RETRY_MAX: Duration = Duration::from_secs(8);
 
 /// Database connection pool wrapper
 #[derive(Clone)]
@@ -37,6 +43,44 @@ impl Database {
         Ok(Database { pool })
     }
 
+    /// Connect, retrying with exponential backoff for up to `connect_retry_timeout` seconds
+    pub async fn connect_with_retry(config: &Config) -> Result<Self> {
+        let deadline = Instant::now() + Duration::from_secs(config.database.connect_retry_timeout);
+        let mut attempt = 0;
+
+        loop {
+            match Self::new(config).await {
+                Ok(db) => return Ok(db),
+                Err(e) => {
+                    let delay = backoff_delay(attempt, CONNECT_RETRY_BASE, CONNECT_RETRY_MAX);
+                    if Instant::now() + delay > deadline {
+                        return Err(e);
+                    }
+
+                    attempt += 1;
+                    warn!(
+                        attempt,
+                        retry_in_ms = delay.as_millis() as u64,
+                        "Database connection failed: {}",
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Create a pool that connects on first use (used when starting without a database)
+    pub fn connect_lazy(config: &Config) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.database.max_connections)
+            .acquire_timeout(Duration::from_secs(10))
+            .connect_lazy(&config.database_url())
+            .map_err(|e| RotaError::DatabaseConnection(e.to_string()))?;
+
+        Ok(Database { pool })
+    }
+
     /// Create a Database wrapper from an existing pool.
     pub fn from_pool(pool: PgPool) -> Self {
         Self { pool }
@@ -84,6 +128,13 @@ impl Database {
     }
 }
 
+/// Delay before retry number `attempt` (0-based): doubles from `base`, capped at `max`
+pub fn backoff_delay(attempt: u32, base: Duration, max: Duration) -> Duration {
+    base.checked_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+        .unwrap_or(max)
+        .min(max)
+}
+
 /// Pool statistics
 #[derive(Debug, Clone)]
 pub struct PoolStats {
@@ -98,3 +149,21 @@ impl std::ops::Deref for Database {
         &self.pool
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay_doubles_and_caps() {
+        let base = Duration::from_millis(500);
+        let max = Duration::from_secs(8);
+
+        assert_eq!(backoff_delay(0, base, max), Duration::from_millis(500));
+        assert_eq!(backoff_delay(1, base, max), Duration::from_secs(1));
+        assert_eq!(backoff_delay(2, base, max), Duration::from_secs(2));
+        assert_eq!(backoff_delay(4, base, max), Duration::from_secs(8));
+        assert_eq!(backoff_delay(5, base, max), Duration::from_secs(8));
+        assert_eq!(backoff_delay(64, base, max), Duration::from_secs(8));
+    }
+}
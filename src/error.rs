@@ -45,6 +45,9 @@ pub enum RotaError {
     #[error("CONNECT failed: {0}")]
     ConnectFailed(String),
 
+    #[error("Egress loop: {0} is this server's own listen address")]
+    EgressLoop(String),
+
     // Authentication errors
     #[error("Authentication failed")]
     AuthenticationFailed,
@@ -61,6 +64,9 @@ pub enum RotaError {
     #[error("Invalid authorization header format")]
     InvalidAuthHeader,
 
+    #[error("Request denied: {0}")]
+    Denied(String),
+
     // Rate limiting
     #[error("Rate limit exceeded for {client_ip}")]
     RateLimitExceeded { client_ip: String },
@@ -76,9 +82,24 @@ pub enum RotaError {
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
+    #[error("Request body too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Request headers too large: {0}")]
+    HeadersTooLarge(String),
+
+    #[error("Upstream response too large: {0}")]
+    ResponseTooLarge(String),
+
+    #[error("Client closed request: {0}")]
+    ClientClosedRequest(String),
+
     #[error("Request timeout")]
     RequestTimeout,
 
+    #[error("Timed out reading request body")]
+    BodyReadTimeout,
+
     #[error("Operation timed out")]
     Timeout,
 
@@ -119,11 +140,26 @@ impl RotaError {
             | RotaError::InvalidAuthHeader
             | RotaError::JwtError(_) => StatusCode::UNAUTHORIZED,
 
+            // 403 Forbidden
+            RotaError::Denied(_) => StatusCode::FORBIDDEN,
+
             // 404 Not Found
             RotaError::ProxyNotFound { .. }
             | RotaError::SettingsNotFound { .. }
             | RotaError::NotFound(_) => StatusCode::NOT_FOUND,
 
+            // 408 Request Timeout
+            RotaError::BodyReadTimeout => StatusCode::REQUEST_TIMEOUT,
+
+            // 413 Payload Too Large
+            RotaError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+
+            // 431 Request Header Fields Too Large
+            RotaError::HeadersTooLarge(_) => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+
+            // 499 Client Closed Request (nginx convention)
+            RotaError::ClientClosedRequest(_) => StatusCode::from_u16(499).unwrap(),
+
             // Timeout
             RotaError::Timeout => StatusCode::GATEWAY_TIMEOUT,
 
@@ -134,8 +170,12 @@ impl RotaError {
             RotaError::ProxyConnectionFailed(_)
             | RotaError::TunnelError(_)
             | RotaError::ConnectFailed(_)
+            | RotaError::ResponseTooLarge(_)
             | RotaError::AllProxiesExhausted { .. } => StatusCode::BAD_GATEWAY,
 
+            // 508 Loop Detected
+            RotaError::EgressLoop(_) => StatusCode::LOOP_DETECTED,
+
             // 503 Service Unavailable
             RotaError::NoProxiesAvailable | RotaError::DatabaseConnection(_) => {
                 StatusCode::SERVICE_UNAVAILABLE
@@ -219,6 +259,28 @@ mod tests {
             .status_code(),
             StatusCode::TOO_MANY_REQUESTS
         );
+        assert_eq!(
+            RotaError::ClientClosedRequest("eof".to_string())
+                .status_code()
+                .as_u16(),
+            499
+        );
+        assert_eq!(
+            RotaError::BodyReadTimeout.status_code(),
+            StatusCode::REQUEST_TIMEOUT
+        );
+        assert_eq!(
+            RotaError::PayloadTooLarge("big".to_string()).status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(
+            RotaError::HeadersTooLarge("big".to_string()).status_code(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+        assert_eq!(
+            RotaError::ResponseTooLarge("big".to_string()).status_code(),
+            StatusCode::BAD_GATEWAY
+        );
         assert_eq!(
             RotaError::Timeout.status_code(),
             StatusCode::GATEWAY_TIMEOUT
@@ -227,6 +289,12 @@ mod tests {
             RotaError::NoProxiesAvailable.status_code(),
             StatusCode::SERVICE_UNAVAILABLE
         );
+        assert_eq!(
+            RotaError::EgressLoop("127.0.0.1:8000".to_string())
+                .status_code()
+                .as_u16(),
+            508
+        );
     }
 
     #[test]
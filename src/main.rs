@@ -7,7 +7,7 @@ use std::time::Duration;
 
 use tokio::signal;
 use tokio::sync::{broadcast, watch};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod api;
@@ -20,76 +20,104 @@ mod repository;
 mod services;
 
 use api::ApiServer;
-use config::Config;
+use config::{Config, StartupHealthcheck};
 use database::Database;
 use proxy::health::{HealthChecker, HealthCheckerConfig, HealthCheckerHandle};
+use proxy::metrics::ProxyMetrics;
 use proxy::middleware::RateLimiter;
-use proxy::rotation::{
-    create_selector, DynamicProxySelector, ProxySelector, RotationStrategy, TimeBasedSelector,
-};
+use proxy::rotation::{create_selector, DynamicProxySelector, ProxySelector, RotationStrategy};
 use proxy::server::ProxyServer;
 use services::{
-    LogCleanupConfig, LogCleanupHandle, LogCleanupService, ProxyAutoDeleteConfig,
-    ProxyAutoDeleteHandle, ProxyAutoDeleteService,
+    GeolocationConfig, GeolocationHandle, GeolocationService, LogCleanupConfig, LogCleanupHandle,
+    LogCleanupService, MaxMindLookup, ProxyAutoDeleteConfig, ProxyAutoDeleteHandle,
+    ProxyAutoDeleteService, ServiceControls,
 };
 
 #[tokio::main]
 async fn main() -> error::Result<()> {
+    // Load configuration
+    let config = Config::from_env()?;
+
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "rota=info,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    init_tracing(&config.log);
 
     info!("Starting Rota Proxy Server");
-
-    // Load configuration
-    let config = Config::from_env()?;
     info!("Configuration loaded");
 
-    // Connect to database
-    let db = Database::new(&config).await?;
-    info!("Connected to database");
-
-    // Run migrations
-    db.run_migrations().await?;
-    info!("Database migrations complete");
+    // Connect to database (retrying for a bounded period)
+    let (db, degraded) = match Database::connect_with_retry(&config).await {
+        Ok(db) => {
+            info!("Connected to database");
+            (db, false)
+        }
+        Err(e) if config.database.fail_open => {
+            warn!(
+                "Database unavailable ({}); starting in degraded mode with an empty proxy pool",
+                e
+            );
+            (Database::connect_lazy(&config)?, true)
+        }
+        Err(e) => return Err(e),
+    };
 
-    // Initialize TimescaleDB if available
-    if let Err(e) = database::timescale::setup_timescaledb(db.pool()).await {
-        info!(
-            "TimescaleDB setup skipped or failed: {} (this is OK if not using TimescaleDB)",
-            e
-        );
+    if !degraded {
+        // Run migrations
+        db.run_migrations().await?;
+        info!("Database migrations complete");
+
+        // Initialize TimescaleDB if available
+        if let Err(e) = database::timescale::setup_timescaledb(db.pool()).await {
+            info!(
+                "TimescaleDB setup skipped or failed: {} (this is OK if not using TimescaleDB)",
+                e
+            );
+        }
     }
 
     // Load runtime settings from DB and expose them via watch channel.
     let settings_repo = repository::SettingsRepository::new(db.pool().clone());
-    let settings = settings_repo.get_all().await?;
+    let settings = if degraded {
+        models::Settings::default()
+    } else {
+        settings_repo.get_all().await?
+    };
     let (settings_tx, _) = watch::channel(settings.clone());
 
     // Create log broadcast channel (bounded to prevent memory leaks)
-    let (log_sender, _) = broadcast::channel::<models::RequestRecord>(1024);
+    let (log_sender, _) = broadcast::channel::<models::RequestRecord>(config.log.stream_buffer);
+    let (status_events, _) =
+        broadcast::channel::<models::ProxyStatusEvent>(config.log.stream_buffer);
 
     // Create proxy selector (strategy can be changed at runtime via settings)
-    let strategy = RotationStrategy::from_str(&settings.rotation.method);
     let interval_secs = settings.rotation.time_based.interval.max(1) as u64;
-    let base_selector: Arc<dyn ProxySelector> = match strategy {
-        RotationStrategy::TimeBased => Arc::new(TimeBasedSelector::with_interval(
+    let selector = Arc::new(DynamicProxySelector::new(Arc::from(create_selector(
+        RotationStrategy::Random,
+    ))));
+    selector.apply_rotation_settings(&settings.rotation).await?;
+    selector
+        .set_host_rules(
+            &settings.rotation.host_rules,
             Duration::from_secs(interval_secs),
-        )),
-        _ => Arc::from(create_selector(strategy)),
-    };
-    let selector = Arc::new(DynamicProxySelector::new(base_selector));
-    info!("Using rotation strategy: {}", strategy.as_str());
+        )
+        .await?;
+    selector.set_recovery_gate(
+        settings.rotation.recovery_min_success_rate,
+        settings.rotation.recovery_window.max(1) as usize,
+    );
+    selector.set_circuit_breaker(
+        settings.rotation.circuit_breaker_failures.max(0) as u32,
+        Duration::from_secs(settings.rotation.circuit_breaker_cooldown.max(1) as u64),
+    );
+    info!("Using rotation strategy: {}", selector.strategy_name());
 
     // Load initial proxies into selector
     let proxy_repo = repository::ProxyRepository::new(db.pool().clone());
-    let proxies = if settings.rotation.remove_unhealthy {
-        proxy_repo.get_all_usable().await?
+    let proxies = if degraded {
+        Vec::new()
+    } else if settings.rotation.remove_unhealthy {
+        proxy_repo
+            .get_all_usable(&settings.rotation.usable_status_filter())
+            .await?
     } else {
         proxy_repo.get_all().await?
     };
@@ -97,7 +125,8 @@ async fn main() -> error::Result<()> {
     info!("Loaded {} proxies", selector.available_count());
 
     // Create shared rate limiter (can be reconfigured at runtime via settings)
-    let rate_limiter = RateLimiter::disabled();
+    let metrics = Arc::new(ProxyMetrics::new());
+    let rate_limiter = RateLimiter::disabled().with_metrics(metrics.clone());
     rate_limiter.apply_settings(&settings.rate_limit);
 
     // Create shutdown channels
@@ -105,20 +134,59 @@ async fn main() -> error::Result<()> {
 
     // Start health checker
     let (health_handle, health_shutdown) = HealthCheckerHandle::new();
-    let health_checker = HealthChecker::new(
-        db.clone(),
-        HealthCheckerConfig::default(),
-        selector.clone(),
-        config.proxy.egress_proxy.clone(),
+    let health_checker = Arc::new(
+        HealthChecker::new(
+            db.clone(),
+            HealthCheckerConfig {
+                start_jitter: Duration::from_secs(config.proxy.healthcheck_start_jitter),
+                jitter_seed: config.proxy.healthcheck_jitter_seed,
+                ..Default::default()
+            },
+            selector.clone(),
+            config.proxy.egress_proxy.clone(),
+        )
+        .with_status_events(status_events.clone())
+        .with_pause(health_handle.pause_flag()),
     );
+    let startup_healthcheck = if degraded {
+        StartupHealthcheck::Off
+    } else {
+        config.proxy.startup_healthcheck
+    };
+    if startup_healthcheck == StartupHealthcheck::Before {
+        startup_health_check(&health_checker, &settings).await;
+    }
+    let health_rounds = health_checker.rounds();
     let health_settings = settings_tx.subscribe();
+    let periodic_checker = health_checker.clone();
     let health_task = tokio::spawn(async move {
-        health_checker.run(health_shutdown, health_settings).await;
+        periodic_checker.run(health_shutdown, health_settings).await;
+    });
+
+    // Apply rate limit changes to the shared limiter
+    let limiter = rate_limiter.clone();
+    let rate_limit_settings = settings_tx.subscribe();
+    let rate_limit_shutdown = shutdown_tx.subscribe();
+    let rate_limit_task = tokio::spawn(async move {
+        limiter
+            .watch_settings(rate_limit_settings, rate_limit_shutdown)
+            .await;
+    });
+
+    // Switch rotation strategies when the settings change
+    let strategy_selector = selector.clone();
+    let strategy_settings = settings_tx.subscribe();
+    let strategy_shutdown = shutdown_tx.subscribe();
+    let strategy_task = tokio::spawn(async move {
+        strategy_selector
+            .watch_settings(strategy_settings, strategy_shutdown)
+            .await;
     });
 
     // Start log cleanup service
     let (cleanup_handle, cleanup_shutdown) = LogCleanupHandle::new();
-    let cleanup_service = LogCleanupService::new(db.clone(), LogCleanupConfig::default());
+    let cleanup_service = LogCleanupService::new(db.clone(), LogCleanupConfig::default())
+        .with_pause(cleanup_handle.pause_flag());
     let cleanup_settings = settings_tx.subscribe();
     let cleanup_task = tokio::spawn(async move {
         cleanup_service
@@ -132,7 +200,8 @@ async fn main() -> error::Result<()> {
         db.clone(),
         selector.clone(),
         ProxyAutoDeleteConfig::default(),
-    );
+    )
+    .with_pause(auto_delete_handle.pause_flag());
     let auto_delete_settings = settings_tx.subscribe();
     let auto_delete_task = tokio::spawn(async move {
         auto_delete_service
@@ -140,15 +209,41 @@ async fn main() -> error::Result<()> {
             .await;
     });
 
+    // Start proxy geolocation service (only when a GeoIP database is configured)
+    let (geolocation_handle, geolocation_shutdown) = GeolocationHandle::new();
+    let geolocation_task = match MaxMindLookup::open(&config.proxy.geoip_databases)? {
+        Some(lookup) if !degraded => {
+            let geolocation_service =
+                GeolocationService::new(db.clone(), Arc::new(lookup), GeolocationConfig::default());
+            Some(tokio::spawn(async move {
+                geolocation_service.run(geolocation_shutdown).await;
+            }))
+        }
+        _ => None,
+    };
+
     // Create proxy server
     let proxy_server = ProxyServer::new(
         config.proxy.clone(),
         selector.clone(),
         db.pool().clone(),
         Some(log_sender.clone()),
+        Some(status_events.clone()),
         rate_limiter.clone(),
+        metrics.clone(),
     );
 
+    // Apply custom rejection bodies now and whenever the settings change
+    let responses = proxy_server.responses();
+    responses.apply_settings(&settings.responses);
+    let responses_settings = settings_tx.subscribe();
+    let responses_shutdown = shutdown_tx.subscribe();
+    let responses_task = tokio::spawn(async move {
+        responses
+            .watch_settings(responses_settings, responses_shutdown)
+            .await;
+    });
+
     // Create API server
     let api_server = ApiServer::new(
         config.api.clone(),
@@ -158,13 +253,32 @@ async fn main() -> error::Result<()> {
         log_sender.clone(),
         settings_tx.clone(),
         rate_limiter.clone(),
-    );
+    )
+    .with_metrics(metrics)
+    .with_upstream_pool(proxy_server.upstream_pool())
+    .with_warm_pool(proxy_server.warm_pool())
+    .with_status_events(status_events)
+    .with_service_controls(ServiceControls::new(vec![
+        health_handle.pause_flag(),
+        cleanup_handle.pause_flag(),
+        auto_delete_handle.pause_flag(),
+    ]));
 
     // Start servers
     let proxy_shutdown = shutdown_tx.subscribe();
     let api_shutdown = shutdown_tx.subscribe();
 
+    let proxy_warmup = warmup(
+        Duration::from_secs(config.proxy.startup_delay),
+        config.proxy.wait_for_health_check,
+        health_rounds,
+    );
     let proxy_task = tokio::spawn(async move {
+        let mut warmup_shutdown = proxy_shutdown.clone();
+        tokio::select! {
+            _ = proxy_warmup => {}
+            _ = warmup_shutdown.wait_for(|stop| *stop) => return,
+        }
         if let Err(e) = proxy_server.run(proxy_shutdown).await {
             error!("Proxy server error: {}", e);
         }
@@ -181,6 +295,13 @@ async fn main() -> error::Result<()> {
         config.proxy.host, config.proxy.port, config.api.host, config.api.port
     );
 
+    if startup_healthcheck == StartupHealthcheck::After {
+        let settings = settings.clone();
+        tokio::spawn(async move {
+            startup_health_check(&health_checker, &settings).await;
+        });
+    }
+
     // Wait for shutdown signal
     shutdown_signal().await;
     info!("Shutdown signal received");
@@ -190,20 +311,80 @@ async fn main() -> error::Result<()> {
     health_handle.shutdown();
     cleanup_handle.shutdown();
     auto_delete_handle.shutdown();
+    geolocation_handle.shutdown();
 
     // Wait for all tasks to complete
     let _ = tokio::join!(
         proxy_task,
         api_task,
         health_task,
+        strategy_task,
+        rate_limit_task,
+        responses_task,
         cleanup_task,
         auto_delete_task
     );
+    if let Some(task) = geolocation_task {
+        let _ = task.await;
+    }
 
     info!("Rota Proxy Server stopped");
     Ok(())
 }
 
+/// Wait until the proxy server may start accepting traffic
+///
+/// Applies PROXY_STARTUP_DELAY, then PROXY_WAIT_FOR_HEALTH_CHECK.
+async fn warmup(
+    delay: Duration,
+    wait_for_health_check: bool,
+    mut health_rounds: watch::Receiver<u64>,
+) {
+    if !delay.is_zero() {
+        info!("Delaying proxy server start by {}s", delay.as_secs());
+        tokio::time::sleep(delay).await;
+    }
+
+    if wait_for_health_check {
+        info!("Waiting for a full health check round before accepting proxy traffic");
+        if health_rounds.wait_for(|rounds| *rounds > 0).await.is_err() {
+            warn!("Health checker stopped before its first round");
+        }
+    }
+}
+
+/// Check every proxy once and log a summary of the pool's initial state
+async fn startup_health_check(checker: &HealthChecker, settings: &models::Settings) {
+    info!("Running startup health check");
+    match checker.check_all_proxies(settings).await {
+        Ok(summary) => info!(
+            healthy = summary.healthy,
+            unhealthy = summary.unhealthy,
+            total = summary.total,
+            "Startup health check: {} healthy, {} unhealthy, {} total",
+            summary.healthy,
+            summary.unhealthy,
+            summary.total
+        ),
+        Err(e) => warn!("Startup health check failed: {}", e),
+    }
+}
+
+/// Install the tracing subscriber, honoring LOG_FORMAT and LOG_LEVEL (RUST_LOG takes precedence)
+fn init_tracing(log: &config::LogConfig) {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| log.default_filter().into());
+    let registry = tracing_subscriber::registry().with(filter);
+
+    if log.is_json() {
+        registry
+            .with(tracing_subscriber::fmt::layer().json())
+            .init();
+    } else {
+        registry.with(tracing_subscriber::fmt::layer()).init();
+    }
+}
+
 /// Wait for shutdown signal (Ctrl+C or SIGTERM)
 async fn shutdown_signal() {
     let ctrl_c = async {
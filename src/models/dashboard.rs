@@ -22,6 +22,73 @@ pub struct DashboardStats {
     pub response_time_delta: i32,
 }
 
+/// Share of requests that met the SLA over a time range
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SlaStats {
+    /// Requests with an SLA tag in the range
+    pub total_requests: i64,
+    /// Requests that succeeded within the SLA threshold
+    pub within_sla: i64,
+    /// Percentage of requests within SLA (0-100)
+    pub within_sla_percent: f64,
+}
+
+impl SlaStats {
+    pub fn new(total_requests: i64, within_sla: i64) -> Self {
+        let within_sla_percent = if total_requests > 0 {
+            (within_sla as f64 / total_requests as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            total_requests,
+            within_sla,
+            within_sla_percent,
+        }
+    }
+}
+
+/// Requests that succeeded on a given attempt
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AttemptBucket {
+    pub attempts: i32,
+    pub requests: i64,
+}
+
+/// How many attempts requests needed over a time range
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RetryStats {
+    /// Successful requests by the attempt they succeeded on, ascending
+    pub succeeded: Vec<AttemptBucket>,
+    /// Requests that failed on every attempt
+    pub failed: i64,
+    /// Percentage of all requests that succeeded on the first attempt (0-100)
+    pub first_attempt_percent: f64,
+}
+
+impl RetryStats {
+    pub fn new(succeeded: Vec<AttemptBucket>, failed: i64) -> Self {
+        let total = succeeded.iter().map(|b| b.requests).sum::<i64>() + failed;
+        let first = succeeded
+            .iter()
+            .filter(|b| b.attempts == 1)
+            .map(|b| b.requests)
+            .sum::<i64>();
+        let first_attempt_percent = if total > 0 {
+            (first as f64 / total as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            succeeded,
+            failed,
+            first_attempt_percent,
+        }
+    }
+}
+
 /// Chart data point
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChartDataPoint {
@@ -126,6 +193,34 @@ pub struct HealthStatus {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sla_stats_percentage() {
+        let stats = SlaStats::new(8, 6);
+        assert_eq!(stats.within_sla_percent, 75.0);
+
+        assert_eq!(SlaStats::new(0, 0).within_sla_percent, 0.0);
+    }
+
+    #[test]
+    fn test_retry_stats_first_attempt_share() {
+        let stats = RetryStats::new(
+            vec![
+                AttemptBucket {
+                    attempts: 1,
+                    requests: 6,
+                },
+                AttemptBucket {
+                    attempts: 2,
+                    requests: 1,
+                },
+            ],
+            1,
+        );
+        assert_eq!(stats.first_attempt_percent, 75.0);
+
+        assert_eq!(RetryStats::new(Vec::new(), 0).first_attempt_percent, 0.0);
+    }
+
     #[test]
     fn test_chart_time_range_start_end_override() {
         let start = Utc::now() - chrono::Duration::hours(2);
@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Log level
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -139,16 +140,56 @@ pub struct RequestRecord {
     pub requested_url: String,
     pub method: String,
     pub success: bool,
+    /// Milliseconds the upstream request through the proxy took
     pub response_time: i32,
+    /// Milliseconds from the gateway receiving the request until its response was ready,
+    /// including queueing, proxy selection and earlier attempts
+    #[serde(default)]
+    pub gateway_latency: i32,
     pub status_code: i32,
     pub error_message: Option<String>,
     pub timestamp: DateTime<Utc>,
+    /// Whether the request succeeded within the configured SLA threshold
+    #[serde(default)]
+    pub within_sla: bool,
+    /// Attempt this record belongs to (1 = first try); overall failures carry the total tried
+    #[serde(default)]
+    pub attempts: i32,
+}
+
+impl RequestRecord {
+    /// A request is within SLA when it succeeded and took at most `threshold`
+    pub fn is_within_sla(success: bool, response_time: Duration, threshold: Duration) -> bool {
+        success && response_time <= threshold
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_request_record_within_sla() {
+        let threshold = Duration::from_secs(2);
+
+        assert!(RequestRecord::is_within_sla(
+            true,
+            Duration::from_millis(1500),
+            threshold
+        ));
+        assert!(RequestRecord::is_within_sla(true, threshold, threshold));
+        assert!(!RequestRecord::is_within_sla(
+            true,
+            Duration::from_millis(2001),
+            threshold
+        ));
+        assert!(!RequestRecord::is_within_sla(
+            false,
+            Duration::from_millis(10),
+            threshold
+        ));
+    }
+
     #[test]
     fn test_log_level_parsing_and_display() {
         assert_eq!(LogLevel::from_str("debug"), Some(LogLevel::Debug));
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::FromRow;
 
+use crate::error::{Result, RotaError};
+
 /// Proxy protocol type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "varchar", rename_all = "lowercase")]
@@ -37,6 +39,13 @@ impl ProxyProtocol {
         }
     }
 
+    /// Canonical lowercase name of a user-supplied protocol, rejecting unknown ones
+    pub fn canonicalize(s: &str) -> Result<&'static str> {
+        Self::from_str(s.trim())
+            .map(|protocol| protocol.as_str())
+            .ok_or_else(|| RotaError::UnsupportedProtocol(s.to_string()))
+    }
+
     pub fn is_socks(&self) -> bool {
         matches!(
             self,
@@ -104,11 +113,29 @@ pub struct Proxy {
     pub username: Option<String>,
     pub password: Option<String>,
     pub status: String, // Stored as string in DB
+    /// Operator switch for rotation, independent of health `status`
+    pub enabled: bool,
+    /// ISO country code from the optional GeoIP database
+    pub country: Option<String>,
+    /// Autonomous system number from the optional GeoIP database
+    pub asn: Option<i64>,
+    /// Operator-assigned tags for grouping proxies
+    pub tags: Vec<String>,
     pub requests: i64,
     pub successful_requests: i64,
     pub failed_requests: i64,
     pub avg_response_time: i32,
+    /// Bytes relayed from clients through the proxy's CONNECT tunnels
+    pub bytes_sent: i64,
+    /// Bytes relayed back to clients through the proxy's CONNECT tunnels
+    pub bytes_received: i64,
     pub last_check: Option<DateTime<Utc>>,
+    /// Last time a health check found the proxy working
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Health check URL for this proxy, overriding the global `healthcheck.url`
+    pub health_check_url: Option<String>,
+    /// Text the health check response must contain, e.g. the proxy's exit country
+    pub expected_response_contains: Option<String>,
     pub last_error: Option<String>,
     pub auto_delete_after_failed_seconds: Option<i32>,
     pub invalid_since: Option<DateTime<Utc>>,
@@ -199,6 +226,8 @@ pub struct ProxyWithStats {
     #[serde(flatten)]
     pub proxy: Proxy,
     pub success_rate: f64,
+    /// Connections currently open through the proxy, as tracked by the selector
+    pub active_connections: usize,
 }
 
 impl From<Proxy> for ProxyWithStats {
@@ -207,6 +236,78 @@ impl From<Proxy> for ProxyWithStats {
         ProxyWithStats {
             proxy,
             success_rate,
+            active_connections: 0,
+        }
+    }
+}
+
+/// Whether a proxy can currently be selected, and why not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProxyEligibility {
+    pub proxy_id: i32,
+    pub eligible: bool,
+    /// Reasons the proxy is kept out of rotation (empty when eligible)
+    pub reasons: Vec<String>,
+    /// Recovered proxy that only gets probe traffic until it proves out
+    pub on_probation: bool,
+}
+
+/// Result of checking one proxy on demand
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyCheckResult {
+    pub healthy: bool,
+    pub response_time_ms: u64,
+    pub error: Option<String>,
+}
+
+/// A proxy's status changed after a health check or a proxied request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyStatusEvent {
+    pub id: i32,
+    pub address: String,
+    pub old_status: String,
+    pub new_status: String,
+    /// Error that failed the proxy, or what brought it back
+    pub reason: String,
+}
+
+/// Aggregated proxy counts and request totals for one protocol
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProtocolHealth {
+    pub protocol: String,
+    pub total: i64,
+    pub active: i64,
+    pub failed: i64,
+    pub idle: i64,
+    pub requests: i64,
+    pub successful_requests: i64,
+}
+
+impl ProtocolHealth {
+    /// Aggregate success rate across all proxies of this protocol (0-100)
+    pub fn success_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            (self.successful_requests as f64 / self.requests as f64) * 100.0
+        }
+    }
+}
+
+/// Protocol health with calculated success rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolHealthSummary {
+    #[serde(flatten)]
+    pub health: ProtocolHealth,
+    pub success_rate: f64,
+}
+
+impl From<ProtocolHealth> for ProtocolHealthSummary {
+    fn from(health: ProtocolHealth) -> Self {
+        let success_rate = health.success_rate();
+        ProtocolHealthSummary {
+            health,
+            success_rate,
         }
     }
 }
@@ -219,6 +320,70 @@ pub struct CreateProxyRequest {
     pub username: Option<String>,
     pub password: Option<String>,
     pub auto_delete_after_failed_seconds: Option<i32>,
+    /// Health check URL overriding the global `healthcheck.url`
+    pub health_check_url: Option<String>,
+    /// Text the health check response must contain
+    pub expected_response_contains: Option<String>,
+}
+
+impl CreateProxyRequest {
+    /// Parse an import line of the form `[scheme://][user[:pass]@]host:port`
+    ///
+    /// Lines without a scheme get `default_protocol`; an explicit scheme always wins.
+    pub fn from_line(line: &str, default_protocol: ProxyProtocol) -> Result<Self> {
+        let line = line.trim();
+        let (protocol, rest) = match line.split_once("://") {
+            Some((scheme, rest)) => (
+                ProxyProtocol::from_str(scheme)
+                    .ok_or_else(|| RotaError::UnsupportedProtocol(scheme.to_string()))?,
+                rest,
+            ),
+            None => (default_protocol, line),
+        };
+
+        let (credentials, address) = match rest.rsplit_once('@') {
+            Some((credentials, address)) => (Some(credentials), address),
+            None => (None, rest),
+        };
+        let address = address.trim_end_matches('/');
+        if address.is_empty() {
+            return Err(RotaError::InvalidProxyAddress(format!(
+                "Missing address in '{}'",
+                line
+            )));
+        }
+        let valid_address = address.rsplit_once(':').is_some_and(|(host, port)| {
+            !host.is_empty() && !host.contains(char::is_whitespace) && port.parse::<u16>().is_ok()
+        });
+        if !valid_address {
+            return Err(RotaError::InvalidProxyAddress(format!(
+                "Expected host:port in '{}'",
+                line
+            )));
+        }
+
+        let (username, password) = match credentials.map(|c| c.split_once(':')) {
+            Some(Some((user, pass))) => (Some(user.to_string()), Some(pass.to_string())),
+            Some(None) => (credentials.map(str::to_string), None),
+            None => (None, None),
+        };
+
+        Ok(Self {
+            address: address.to_string(),
+            protocol: protocol.as_str().to_string(),
+            username,
+            password,
+            auto_delete_after_failed_seconds: None,
+            health_check_url: None,
+            expected_response_contains: None,
+        })
+    }
+
+    /// Store the protocol under its canonical name so the transport can match it
+    pub fn normalize_protocol(&mut self) -> Result<()> {
+        self.protocol = ProxyProtocol::canonicalize(&self.protocol)?.to_string();
+        Ok(())
+    }
 }
 
 /// Request to update an existing proxy
@@ -229,6 +394,21 @@ pub struct UpdateProxyRequest {
     pub username: Option<String>,
     pub password: Option<String>,
     pub status: Option<String>,
+    pub enabled: Option<bool>,
+    /// New health check URL; an empty string clears the override
+    pub health_check_url: Option<String>,
+    /// New expected health check content; an empty string clears it
+    pub expected_response_contains: Option<String>,
+}
+
+impl UpdateProxyRequest {
+    /// Store a changed protocol under its canonical name so the transport can match it
+    pub fn normalize_protocol(&mut self) -> Result<()> {
+        if let Some(protocol) = &self.protocol {
+            self.protocol = Some(ProxyProtocol::canonicalize(protocol)?.to_string());
+        }
+        Ok(())
+    }
 }
 
 /// Archived proxy (automatically deleted and moved out of the active pool)
@@ -246,6 +426,8 @@ pub struct DeletedProxy {
     pub successful_requests: i64,
     pub failed_requests: i64,
     pub avg_response_time: i32,
+    pub bytes_sent: i64,
+    pub bytes_received: i64,
     pub last_check: Option<DateTime<Utc>>,
     #[serde(skip_serializing)]
     pub last_error: Option<String>,
@@ -268,7 +450,58 @@ pub struct DeletedProxyListParams {
 /// Bulk create proxies request
 #[derive(Debug, Clone, Deserialize)]
 pub struct BulkCreateProxiesRequest {
+    #[serde(default)]
     pub proxies: Vec<CreateProxyRequest>,
+    /// Raw import lines (`[scheme://][user[:pass]@]host:port`)
+    #[serde(default)]
+    pub lines: Vec<String>,
+    /// Protocol for `lines` without a scheme; defaults to `ROTA_DEFAULT_PROXY_PROTOCOL`
+    #[serde(default)]
+    pub default_protocol: Option<String>,
+}
+
+/// Parse a newline-delimited proxy list, one `[scheme://][user[:pass]@]host:port` per line
+///
+/// Blank lines and `#` comments are ignored. Malformed lines are reported with their
+/// 1-based line number instead of failing the whole list.
+pub fn parse_proxy_list(
+    text: &str,
+    default_protocol: ProxyProtocol,
+) -> (Vec<CreateProxyRequest>, Vec<ProxyImportError>) {
+    let mut requests = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match CreateProxyRequest::from_line(line, default_protocol) {
+            Ok(req) => requests.push(req),
+            Err(e) => errors.push(ProxyImportError {
+                line: index + 1,
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    (requests, errors)
+}
+
+/// A proxy list line that could not be imported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyImportError {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Outcome of importing a proxy list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyImportSummary {
+    pub created: usize,
+    /// Well-formed lines that were not created, e.g. addresses already in the pool
+    pub skipped: usize,
+    pub errors: Vec<ProxyImportError>,
 }
 
 /// Bulk delete proxies request
@@ -277,6 +510,22 @@ pub struct BulkDeleteProxiesRequest {
     pub ids: Vec<i32>,
 }
 
+/// Add and remove tags across many proxies
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkTagProxiesRequest {
+    pub ids: Vec<i32>,
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// Enable or disable every proxy with a tag
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetTagEnabledRequest {
+    pub enabled: bool,
+}
+
 /// Proxy list query parameters
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct ProxyListParams {
@@ -285,6 +534,9 @@ pub struct ProxyListParams {
     pub search: Option<String>,
     pub status: Option<String>,
     pub protocol: Option<String>,
+    pub country: Option<String>,
+    pub asn: Option<i64>,
+    pub tag: Option<String>,
     pub sort_field: Option<String>,
     pub sort_order: Option<String>,
 }
@@ -325,11 +577,20 @@ mod tests {
             username: None,
             password: None,
             status: "idle".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
             requests: 0,
             successful_requests: 0,
             failed_requests: 0,
             avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
             last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
             last_error: None,
             auto_delete_after_failed_seconds: None,
             invalid_since: None,
@@ -489,4 +750,155 @@ mod tests {
         let resp = PaginatedResponse::new(vec![1; 10], 11, 1, 10);
         assert_eq!(resp.total_pages, 2);
     }
+
+    #[test]
+    fn test_protocol_health_summary_success_rate() {
+        let health = ProtocolHealth {
+            protocol: "socks5".to_string(),
+            total: 4,
+            active: 1,
+            failed: 3,
+            idle: 0,
+            requests: 200,
+            successful_requests: 50,
+        };
+
+        let summary = ProtocolHealthSummary::from(health);
+        assert_eq!(summary.success_rate, 25.0);
+
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(value["protocol"], "socks5");
+        assert_eq!(value["failed"], 3);
+        assert_eq!(value["success_rate"], 25.0);
+
+        let idle = ProtocolHealth {
+            requests: 0,
+            successful_requests: 0,
+            ..summary.health
+        };
+        assert_eq!(idle.success_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_from_line_schemeless_uses_default_protocol() {
+        let req = CreateProxyRequest::from_line("10.0.0.1:1080", ProxyProtocol::Socks5).unwrap();
+        assert_eq!(req.address, "10.0.0.1:1080");
+        assert_eq!(req.protocol, "socks5");
+        assert_eq!(req.username, None);
+
+        let req = CreateProxyRequest::from_line(" user:pass@10.0.0.2:1080 ", ProxyProtocol::Socks4)
+            .unwrap();
+        assert_eq!(req.address, "10.0.0.2:1080");
+        assert_eq!(req.protocol, "socks4");
+        assert_eq!(req.username.as_deref(), Some("user"));
+        assert_eq!(req.password.as_deref(), Some("pass"));
+    }
+
+    #[test]
+    fn test_from_line_explicit_scheme_wins() {
+        let req =
+            CreateProxyRequest::from_line("http://10.0.0.1:8080", ProxyProtocol::Socks5).unwrap();
+        assert_eq!(req.protocol, "http");
+        assert_eq!(req.address, "10.0.0.1:8080");
+
+        let req = CreateProxyRequest::from_line("SOCKS4A://u@10.0.0.1:1080/", ProxyProtocol::Http)
+            .unwrap();
+        assert_eq!(req.protocol, "socks4a");
+        assert_eq!(req.address, "10.0.0.1:1080");
+        assert_eq!(req.username.as_deref(), Some("u"));
+        assert_eq!(req.password, None);
+
+        assert!(matches!(
+            CreateProxyRequest::from_line("ftp://10.0.0.1:21", ProxyProtocol::Http),
+            Err(RotaError::UnsupportedProtocol(_))
+        ));
+    }
+
+    #[test]
+    fn test_normalize_protocol_canonicalizes_or_rejects() {
+        let mut req = CreateProxyRequest::from_line("10.0.0.1:8080", ProxyProtocol::Http).unwrap();
+        req.protocol = "HTTP".to_string();
+        req.normalize_protocol().unwrap();
+        assert_eq!(req.protocol, "http");
+
+        req.protocol = "htto".to_string();
+        assert!(matches!(
+            req.normalize_protocol(),
+            Err(RotaError::UnsupportedProtocol(p)) if p == "htto"
+        ));
+
+        let mut update = UpdateProxyRequest {
+            address: None,
+            protocol: Some(" Socks5 ".to_string()),
+            username: None,
+            password: None,
+            status: None,
+            enabled: None,
+            health_check_url: None,
+            expected_response_contains: None,
+        };
+        update.normalize_protocol().unwrap();
+        assert_eq!(update.protocol.as_deref(), Some("socks5"));
+
+        update.protocol = Some("htto".to_string());
+        assert!(update.normalize_protocol().is_err());
+
+        update.protocol = None;
+        update.normalize_protocol().unwrap();
+        assert_eq!(update.protocol, None);
+    }
+
+    #[test]
+    fn test_parse_proxy_list_forms() {
+        let text = "\
+# provider list
+socks5://10.0.0.1:1080
+user:p@ss@10.0.0.2:3128
+
+10.0.0.3:8080
+HTTPS://u:p@proxy.example:443
+";
+        let (requests, errors) = parse_proxy_list(text, ProxyProtocol::Http);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let summary: Vec<(&str, &str, Option<&str>, Option<&str>)> = requests
+            .iter()
+            .map(|r| {
+                (
+                    r.protocol.as_str(),
+                    r.address.as_str(),
+                    r.username.as_deref(),
+                    r.password.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("socks5", "10.0.0.1:1080", None, None),
+                ("http", "10.0.0.2:3128", Some("user"), Some("p@ss")),
+                ("http", "10.0.0.3:8080", None, None),
+                ("https", "proxy.example:443", Some("u"), Some("p")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_proxy_list_reports_malformed_lines() {
+        let text = "10.0.0.1:8080\nftp://10.0.0.2:21\nnot a proxy\n10.0.0.3\n10.0.0.4:99999\n10.0.0.5:1080";
+        let (requests, errors) = parse_proxy_list(text, ProxyProtocol::Socks5);
+
+        assert_eq!(
+            requests
+                .iter()
+                .map(|r| r.address.as_str())
+                .collect::<Vec<_>>(),
+            vec!["10.0.0.1:8080", "10.0.0.5:1080"]
+        );
+        assert_eq!(
+            errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            vec![2, 3, 4, 5]
+        );
+        assert!(errors[0].reason.contains("ftp"));
+    }
 }
@@ -1,5 +1,9 @@
+use hyper::header::{HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
 
+use super::ProxyStatus;
+use crate::error::{Result, RotaError};
+
 /// Complete application settings
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Settings {
@@ -8,6 +12,45 @@ pub struct Settings {
     pub rate_limit: RateLimitSettings,
     pub healthcheck: HealthCheckSettings,
     pub log_retention: LogRetentionSettings,
+    #[serde(default)]
+    pub cors: CorsSettings,
+    #[serde(default)]
+    pub responses: ResponseSettings,
+}
+
+impl Settings {
+    /// Reject settings that can't be applied as given
+    pub fn validate(&self) -> Result<()> {
+        self.healthcheck.parsed_headers()?;
+        let url_count = self.healthcheck.urls().len();
+        if url_count > 0 && usize::try_from(self.healthcheck.quorum).is_ok_and(|q| q > url_count) {
+            return Err(RotaError::InvalidRequest(format!(
+                "healthcheck.quorum {} exceeds the {} check URLs",
+                self.healthcheck.quorum, url_count
+            )));
+        }
+        if self.healthcheck.mode == HealthCheckMode::HttpRequest {
+            if let Some(url) = self
+                .healthcheck
+                .urls()
+                .into_iter()
+                .find(|url| !is_inspectable_check_url(url))
+            {
+                return Err(RotaError::InvalidRequest(format!(
+                    "healthcheck.mode http_request needs http:// check URLs, got '{}'",
+                    url
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a health check can read the response from `url`
+///
+/// Responses are read straight off the tunnel without TLS, so only `http://` URLs qualify.
+pub fn is_inspectable_check_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|u| u.scheme() == "http")
 }
 
 /// Proxy server authentication settings
@@ -37,10 +80,14 @@ impl Default for AuthenticationSettings {
 /// Proxy rotation configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RotationSettings {
-    /// Rotation method: random, roundrobin, least_conn, time_based
+    /// Rotation method: random, roundrobin, least_conn, time_based, latency_weighted,
+    /// sticky_until_failure, weighted_random, composite
     pub method: String,
     /// Time-based rotation settings
     pub time_based: TimeBasedSettings,
+    /// Score weights for the composite strategy
+    #[serde(default)]
+    pub composite: CompositeSettings,
     /// Remove unhealthy proxies from rotation
     pub remove_unhealthy: bool,
     /// Enable fallback to next proxy on failure
@@ -59,6 +106,30 @@ pub struct RotationSettings {
     pub max_response_time: i32,
     /// Minimum success rate percentage (0-100, 0 = no minimum)
     pub min_success_rate: f64,
+    /// Least-connections tie-break: first, lowest_latency, round_robin, random
+    #[serde(default)]
+    pub least_connections_tie_break: String,
+    /// Active connections per proxy before least-connections skips it (0 = unlimited)
+    #[serde(default)]
+    pub max_connections_per_proxy: i32,
+    /// Success rate percentage a recovered proxy needs before leaving probation (0 = off)
+    #[serde(default)]
+    pub recovery_min_success_rate: f64,
+    /// Number of recent requests the recovery success rate is computed over
+    #[serde(default = "default_recovery_window")]
+    pub recovery_window: i32,
+    /// Consecutive failed requests after which a proxy is benched (0 = circuit breaker off)
+    #[serde(default)]
+    pub circuit_breaker_failures: i32,
+    /// Seconds a benched proxy waits before it gets one trial request
+    #[serde(default = "default_circuit_breaker_cooldown")]
+    pub circuit_breaker_cooldown: i32,
+    /// Per-target-host strategy overrides, first match wins
+    #[serde(default)]
+    pub host_rules: Vec<HostStrategyRule>,
+    /// Proxy statuses eligible for rotation: `active` and/or `idle` (never-checked)
+    #[serde(default = "default_usable_statuses")]
+    pub usable_statuses: Vec<String>,
 }
 
 impl Default for RotationSettings {
@@ -66,6 +137,7 @@ impl Default for RotationSettings {
         Self {
             method: "random".to_string(),
             time_based: TimeBasedSettings::default(),
+            composite: CompositeSettings::default(),
             remove_unhealthy: true,
             fallback: true,
             fallback_max_retries: 3,
@@ -75,6 +147,79 @@ impl Default for RotationSettings {
             allowed_protocols: vec![],
             max_response_time: 0,
             min_success_rate: 0.0,
+            least_connections_tie_break: String::new(),
+            max_connections_per_proxy: 0,
+            recovery_min_success_rate: 0.0,
+            recovery_window: default_recovery_window(),
+            circuit_breaker_failures: 0,
+            circuit_breaker_cooldown: default_circuit_breaker_cooldown(),
+            host_rules: vec![],
+            usable_statuses: default_usable_statuses(),
+        }
+    }
+}
+
+impl RotationSettings {
+    /// Normalized `usable_statuses`, falling back to `active` and `idle` when none are valid
+    pub fn usable_status_filter(&self) -> Vec<String> {
+        let mut statuses: Vec<String> = Vec::new();
+        for status in &self.usable_statuses {
+            let Some(status) = ProxyStatus::from_str(status.trim()).filter(|s| s.is_usable())
+            else {
+                continue;
+            };
+            let status = status.as_str().to_string();
+            if !statuses.contains(&status) {
+                statuses.push(status);
+            }
+        }
+
+        if statuses.is_empty() {
+            default_usable_statuses()
+        } else {
+            statuses
+        }
+    }
+}
+
+fn default_recovery_window() -> i32 {
+    10
+}
+
+fn default_circuit_breaker_cooldown() -> i32 {
+    30
+}
+
+fn default_usable_statuses() -> Vec<String> {
+    vec![
+        ProxyStatus::Active.as_str().to_string(),
+        ProxyStatus::Idle.as_str().to_string(),
+    ]
+}
+
+/// Rotation strategy override for target hosts matching a pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostStrategyRule {
+    /// Host pattern: exact host, `*.example.com` for subdomains, or `*` for any host
+    pub pattern: String,
+    /// Rotation method used for matching hosts
+    pub method: String,
+}
+
+impl HostStrategyRule {
+    /// Check whether a target host matches this rule's pattern
+    pub fn matches(&self, host: &str) -> bool {
+        let pattern = self.pattern.trim().to_lowercase();
+        let host = host.trim_end_matches('.').to_lowercase();
+
+        if pattern == "*" {
+            return true;
+        }
+        match pattern.strip_prefix("*.") {
+            Some(suffix) => host
+                .strip_suffix(suffix)
+                .is_some_and(|prefix| prefix.ends_with('.')),
+            None => host == pattern,
         }
     }
 }
@@ -92,6 +237,28 @@ impl Default for TimeBasedSettings {
     }
 }
 
+/// Composite rotation settings: how much each signal counts towards a proxy's score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompositeSettings {
+    pub success_rate_weight: f64,
+    pub latency_weight: f64,
+    pub connections_weight: f64,
+    /// Pick proxies with probability proportional to their score instead of the best one
+    pub proportional: bool,
+}
+
+impl Default for CompositeSettings {
+    fn default() -> Self {
+        Self {
+            success_rate_weight: 0.5,
+            latency_weight: 0.3,
+            connections_weight: 0.2,
+            proportional: false,
+        }
+    }
+}
+
 /// Rate limiting configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitSettings {
@@ -101,6 +268,32 @@ pub struct RateLimitSettings {
     pub interval: i32,
     /// Maximum requests per interval
     pub max_requests: i32,
+    /// Only count requests that would be blocked instead of rejecting them
+    #[serde(default)]
+    pub observe_only: bool,
+    /// Also limit requests from all clients combined
+    #[serde(default)]
+    pub global_enabled: bool,
+    /// Maximum requests per interval across all clients
+    #[serde(default)]
+    pub global_max_requests: i32,
+    /// Most clients tracked at once; the least recently seen are evicted beyond it (0 = no cap)
+    #[serde(default)]
+    pub max_clients: i32,
+    /// What identifies a client for its own quota
+    #[serde(default)]
+    pub key: RateLimitKey,
+}
+
+/// What a per-client rate limit is keyed by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    /// The client's IP address
+    #[default]
+    ClientIp,
+    /// The verified `Proxy-Authorization` username, or the IP for requests without one
+    AuthUser,
 }
 
 impl Default for RateLimitSettings {
@@ -109,37 +302,128 @@ impl Default for RateLimitSettings {
             enabled: false,
             interval: 60,
             max_requests: 100,
+            observe_only: false,
+            global_enabled: false,
+            global_max_requests: 1000,
+            max_clients: 100_000,
+            key: RateLimitKey::ClientIp,
         }
     }
 }
 
+/// How a health check decides a proxy is healthy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthCheckMode {
+    /// Healthy once a tunnel to the check URL's host is open
+    #[default]
+    ConnectOnly,
+    /// Fetch the check URL through the tunnel and require the expected status
+    HttpRequest,
+}
+
 /// Health check configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthCheckSettings {
+    /// How proxies are checked
+    #[serde(default)]
+    pub mode: HealthCheckMode,
     /// Timeout in seconds
     pub timeout: i32,
     /// Number of concurrent health check workers
     pub workers: i32,
-    /// URL to check
+    /// URL to check, or a comma-separated list of URLs
     pub url: String,
-    /// Expected HTTP status code
+    /// How many of the check URLs a proxy must reach to be healthy (0 = 1)
+    #[serde(default)]
+    pub quorum: i32,
+    /// Expected HTTP status code (`http_request` mode)
     pub status: i32,
-    /// Custom headers
+    /// Text the response must contain in `http_request` mode (empty = any)
+    #[serde(default)]
+    pub expected_body: String,
+    /// Extra headers sent with HTTP health checks, each as `Name: Value`
     pub headers: Vec<String>,
+    /// Checks slower than this many milliseconds count as failures (0 = no limit)
+    #[serde(default)]
+    pub max_latency_ms: i32,
+    /// Don't apply a round's failures when they exceed this fraction of the pool (0 = off)
+    #[serde(default)]
+    pub mass_failure_threshold: f64,
+    /// Skip proxies that passed a check within this many seconds (0 = check every round)
+    #[serde(default)]
+    pub verified_freshness_secs: i32,
+    /// Connect to proxies directly instead of through the egress proxy
+    #[serde(default)]
+    pub bypass_egress: bool,
 }
 
 impl Default for HealthCheckSettings {
     fn default() -> Self {
         Self {
+            mode: HealthCheckMode::ConnectOnly,
             timeout: 10,
             workers: 20,
             url: "https://httpbin.org/ip".to_string(),
+            quorum: 1,
             status: 200,
+            expected_body: String::new(),
             headers: vec![],
+            max_latency_ms: 0,
+            mass_failure_threshold: 0.0,
+            verified_freshness_secs: 0,
+            bypass_egress: false,
         }
     }
 }
 
+impl HealthCheckSettings {
+    /// Check URLs listed in `url`, without blanks
+    pub fn urls(&self) -> Vec<&str> {
+        self.url
+            .split(',')
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .collect()
+    }
+
+    /// URLs that must pass out of `url_count`: `quorum`, at least 1 and at most all of them
+    pub fn effective_quorum(&self, url_count: usize) -> usize {
+        (self.quorum.max(1) as usize).min(url_count.max(1))
+    }
+
+    /// `headers` parsed into name/value pairs, failing on the first malformed entry
+    pub fn parsed_headers(&self) -> Result<Vec<(HeaderName, HeaderValue)>> {
+        self.headers
+            .iter()
+            .map(|entry| parse_header_entry(entry))
+            .collect()
+    }
+}
+
+/// Parse a `Name: Value` header entry; whitespace around the name and value is ignored
+pub fn parse_header_entry(entry: &str) -> Result<(HeaderName, HeaderValue)> {
+    let invalid = |reason: String| {
+        RotaError::InvalidRequest(format!(
+            "Invalid healthcheck header '{}': {}",
+            entry, reason
+        ))
+    };
+
+    let (name, value) = entry
+        .split_once(':')
+        .ok_or_else(|| invalid("expected 'Name: Value'".to_string()))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(invalid("missing header name".to_string()));
+    }
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| invalid(format!("'{}' is not a valid header name", name)))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|_| invalid("value contains invalid characters".to_string()))?;
+    Ok((name, value))
+}
+
 /// Log retention and cleanup configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogRetentionSettings {
@@ -164,6 +448,41 @@ impl Default for LogRetentionSettings {
     }
 }
 
+/// API CORS configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CorsSettings {
+    /// Allowed browser origins (empty = use CORS_ORIGINS from the environment)
+    pub allowed_origins: Vec<String>,
+}
+
+/// Custom bodies for the responses the proxy rejects requests with
+///
+/// Unset entries keep the built-in plain-text messages.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ResponseSettings {
+    /// 429 for clients over the rate limit
+    pub rate_limited: Option<ResponseTemplate>,
+    /// 503 when no proxy is available to take the request
+    pub unavailable: Option<ResponseTemplate>,
+    /// 502 when every attempt through a proxy failed
+    pub all_failed: Option<ResponseTemplate>,
+}
+
+/// A response body, given inline or read from a file
+///
+/// `{{status}}` and `{{message}}` in the body are replaced by the status code and the
+/// built-in message.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ResponseTemplate {
+    /// Content-Type of the body (empty = text/plain)
+    pub content_type: String,
+    pub body: String,
+    /// File to read the body from instead of `body`, read again whenever settings change
+    pub file: Option<String>,
+}
+
 /// Settings database record
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct SettingsRecord {
@@ -179,6 +498,8 @@ pub mod keys {
     pub const RATE_LIMIT: &str = "rate_limit";
     pub const HEALTHCHECK: &str = "healthcheck";
     pub const LOG_RETENTION: &str = "log_retention";
+    pub const CORS: &str = "cors";
+    pub const RESPONSES: &str = "responses";
 }
 
 #[cfg(test)]
@@ -235,4 +556,132 @@ mod tests {
             .and_then(|v| v.get("password"))
             .is_none());
     }
+
+    #[test]
+    fn test_healthcheck_headers_parse() {
+        let (name, value) = parse_header_entry("X-Probe-Token:  abc 123 ").unwrap();
+        assert_eq!(name, "x-probe-token");
+        assert_eq!(value, "abc 123");
+
+        let (name, value) = parse_header_entry("Accept:").unwrap();
+        assert_eq!(name, "accept");
+        assert_eq!(value, "");
+
+        // Only the first colon separates name from value
+        let (_, value) = parse_header_entry("Referer: http://example.com/").unwrap();
+        assert_eq!(value, "http://example.com/");
+    }
+
+    #[test]
+    fn test_healthcheck_headers_reject_malformed_entries() {
+        for entry in [
+            "no separator",
+            ": value",
+            "Bad Name: value",
+            "X-Line: one\r\nInjected: two",
+        ] {
+            assert!(
+                matches!(parse_header_entry(entry), Err(RotaError::InvalidRequest(_))),
+                "{} accepted",
+                entry
+            );
+        }
+
+        let mut settings = Settings::default();
+        settings.healthcheck.headers = vec!["Accept: */*".to_string()];
+        assert!(settings.validate().is_ok());
+        settings.healthcheck.headers.push("broken".to_string());
+        let error = settings.validate().unwrap_err().to_string();
+        assert!(error.contains("'broken'"), "{}", error);
+    }
+
+    #[test]
+    fn test_healthcheck_url_list_and_quorum() {
+        let mut settings = Settings::default();
+        settings.healthcheck.url = " http://a.example/ip, ,http://b.example/ip ".to_string();
+        assert_eq!(
+            settings.healthcheck.urls(),
+            vec!["http://a.example/ip", "http://b.example/ip"]
+        );
+        assert_eq!(settings.healthcheck.effective_quorum(2), 1);
+
+        settings.healthcheck.quorum = 0;
+        assert_eq!(settings.healthcheck.effective_quorum(2), 1);
+        settings.healthcheck.quorum = 2;
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.healthcheck.effective_quorum(1), 1);
+        settings.healthcheck.quorum = 3;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_http_request_mode_needs_http_check_urls() {
+        let mut settings = Settings::default();
+        settings.healthcheck.mode = HealthCheckMode::HttpRequest;
+        // The default check URL is https, which the check can't read responses from
+        assert!(settings.validate().is_err());
+
+        settings.healthcheck.url = "http://a.example/ip,https://b.example/ip".to_string();
+        let error = settings.validate().unwrap_err().to_string();
+        assert!(error.contains("'https://b.example/ip'"), "{}", error);
+
+        settings.healthcheck.url = "http://a.example/ip,HTTP://b.example/ip".to_string();
+        assert!(settings.validate().is_ok());
+
+        settings.healthcheck.mode = HealthCheckMode::ConnectOnly;
+        settings.healthcheck.url = "https://a.example/ip".to_string();
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_host_strategy_rule_matching() {
+        let rule = |pattern: &str| HostStrategyRule {
+            pattern: pattern.to_string(),
+            method: "round_robin".to_string(),
+        };
+
+        assert!(rule("*.example.com").matches("api.example.com"));
+        assert!(rule("*.example.com").matches("a.b.Example.COM"));
+        assert!(!rule("*.example.com").matches("example.com"));
+        assert!(!rule("*.example.com").matches("badexample.com"));
+        assert!(rule("example.com").matches("example.com"));
+        assert!(!rule("example.com").matches("api.example.com"));
+        assert!(rule("*").matches("anything.org"));
+    }
+
+    #[test]
+    fn test_usable_statuses_default_includes_idle() {
+        let settings: RotationSettings = serde_json::from_value(serde_json::json!({
+            "method": "random",
+            "time_based": { "interval": 60 },
+            "remove_unhealthy": true,
+            "fallback": true,
+            "fallback_max_retries": 3,
+            "follow_redirect": true,
+            "timeout": 30,
+            "retries": 2,
+            "allowed_protocols": [],
+            "max_response_time": 0,
+            "min_success_rate": 0.0
+        }))
+        .unwrap();
+
+        assert_eq!(settings.usable_status_filter(), vec!["active", "idle"]);
+    }
+
+    #[test]
+    fn test_usable_statuses_active_only() {
+        let settings = RotationSettings {
+            usable_statuses: vec!["ACTIVE".to_string(), "active".to_string()],
+            ..RotationSettings::default()
+        };
+        assert_eq!(settings.usable_status_filter(), vec!["active"]);
+
+        // Failed proxies only rotate through `remove_unhealthy = false`.
+        let settings = RotationSettings {
+            usable_statuses: vec!["failed".to_string(), "bogus".to_string()],
+            ..RotationSettings::default()
+        };
+        assert_eq!(settings.usable_status_filter(), vec!["active", "idle"]);
+    }
 }
@@ -1,4 +1,7 @@
+use std::net::{IpAddr, SocketAddr};
+
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use parking_lot::RwLock;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio_socks::tcp::Socks5Stream;
@@ -6,6 +9,17 @@ use tokio_socks::tcp::Socks5Stream;
 use crate::config::{EgressProxyConfig, EgressProxyProtocol};
 use crate::error::{Result, RotaError};
 
+/// Listen addresses of this server; dialing one of them would loop traffic back to us
+static SELF_ADDRESSES: RwLock<Vec<SocketAddr>> = parking_lot::const_rwlock(Vec::new());
+
+/// Register a local listen address that outgoing connections must never target
+pub fn register_self_address(addr: SocketAddr) {
+    let mut addrs = SELF_ADDRESSES.write();
+    if !addrs.contains(&addr) {
+        addrs.push(addr);
+    }
+}
+
 pub async fn connect_to_addr(
     egress_proxy: Option<&EgressProxyConfig>,
     addr: &str,
@@ -22,11 +36,18 @@ pub async fn connect_to_host_port(
     let direct_addr = format_tcp_addr(host, port);
 
     let Some(egress_proxy) = egress_proxy else {
-        return TcpStream::connect(&direct_addr)
+        let addrs = resolve_checked(host, port).await?;
+        return TcpStream::connect(&addrs[..])
             .await
             .map_err(|e| RotaError::ProxyConnectionFailed(format!("TCP connect failed: {}", e)));
     };
 
+    // The egress proxy dials the target itself, so only literal IPs can be checked here.
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        check_not_self(SocketAddr::new(ip, port))?;
+    }
+    resolve_checked(&egress_proxy.host, egress_proxy.port).await?;
+
     let proxy_addr = format_tcp_addr(&egress_proxy.host, egress_proxy.port);
 
     match egress_proxy.protocol {
@@ -51,6 +72,34 @@ pub async fn connect_to_host_port(
     }
 }
 
+/// Resolve `host:port`, failing with `EgressLoop` if any address is one of our own
+async fn resolve_checked(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host(format_tcp_addr(host, port))
+        .await
+        .map_err(|e| RotaError::ProxyConnectionFailed(format!("TCP connect failed: {}", e)))?
+        .collect();
+
+    for addr in &addrs {
+        check_not_self(*addr)?;
+    }
+    Ok(addrs)
+}
+
+fn check_not_self(target: SocketAddr) -> Result<()> {
+    let is_self = SELF_ADDRESSES.read().iter().any(|own| {
+        own.port() == target.port()
+            && (own.ip() == target.ip()
+                || (own.ip().is_unspecified()
+                    && (target.ip().is_loopback() || target.ip().is_unspecified())))
+    });
+
+    if is_self {
+        Err(RotaError::EgressLoop(target.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
 async fn connect_via_http_proxy(
     proxy: &EgressProxyConfig,
     proxy_addr: &str,
@@ -144,7 +193,7 @@ fn format_tcp_addr(host: &str, port: u16) -> String {
     }
 }
 
-fn format_connect_authority(host: &str, port: u16) -> String {
+pub(crate) fn format_connect_authority(host: &str, port: u16) -> String {
     if host.contains(':') && !(host.starts_with('[') && host.ends_with(']')) {
         format!("[{}]:{}", host, port)
     } else {
@@ -172,6 +221,34 @@ mod tests {
         assert_eq!(port, 8080);
     }
 
+    #[tokio::test]
+    async fn connect_to_self_address_is_rejected() {
+        // Addresses no other test listens on, so registering them is harmless.
+        register_self_address("127.0.0.254:1".parse().unwrap());
+        register_self_address("0.0.0.0:2".parse().unwrap());
+
+        let err = connect_to_host_port(None, "127.0.0.254", 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RotaError::EgressLoop(_)));
+
+        // A wildcard listen address also covers loopback targets.
+        let err = connect_to_addr(None, "127.0.0.1:2").await.unwrap_err();
+        assert!(matches!(err, RotaError::EgressLoop(_)));
+
+        let cfg = EgressProxyConfig {
+            protocol: EgressProxyProtocol::Http,
+            host: "127.0.0.254".to_string(),
+            port: 1,
+            username: None,
+            password: None,
+        };
+        let err = connect_to_host_port(Some(&cfg), "example.com", 80)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RotaError::EgressLoop(_)));
+    }
+
     #[tokio::test]
     async fn connect_via_http_proxy_tunnels_bytes() {
         // Start an echo target.
@@ -0,0 +1,171 @@
+//! Proxy traffic counters exported in the Prometheus text format
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use hyper::StatusCode;
+use parking_lot::Mutex;
+
+/// Counters shared by the proxy handler, the rate limiter and the `/metrics` endpoint
+#[derive(Debug, Default)]
+pub struct ProxyMetrics {
+    requests: AtomicU64,
+    responses: Mutex<BTreeMap<u16, u64>>,
+    active_tunnels: AtomicU64,
+    rate_limited: AtomicU64,
+    dropped_broadcasts: AtomicU64,
+    dropped_persists: AtomicU64,
+}
+
+impl ProxyMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a proxied request answered with `status`
+    pub fn record_response(&self, status: StatusCode) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        *self.responses.lock().entry(status.as_u16()).or_insert(0) += 1;
+    }
+
+    /// Count a request rejected by the rate limiter
+    pub fn record_rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count `count` request records a live log subscriber skipped by falling behind
+    pub fn record_dropped_broadcasts(&self, count: u64) {
+        self.dropped_broadcasts.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Count a request record that could not be written to the database
+    pub fn record_dropped_persist(&self) {
+        self.dropped_persists.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an open tunnel until the returned guard is dropped
+    pub fn tunnel_opened(self: &Arc<Self>) -> ActiveTunnel {
+        self.active_tunnels.fetch_add(1, Ordering::Relaxed);
+        ActiveTunnel(self.clone())
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn active_tunnels(&self) -> u64 {
+        self.active_tunnels.load(Ordering::Relaxed)
+    }
+
+    pub fn rate_limited(&self) -> u64 {
+        self.rate_limited.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped_broadcasts(&self) -> u64 {
+        self.dropped_broadcasts.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped_persists(&self) -> u64 {
+        self.dropped_persists.load(Ordering::Relaxed)
+    }
+
+    /// Render every metric, with `available_proxies` as reported by the selector
+    pub fn render(&self, available_proxies: usize) -> String {
+        let mut out = String::new();
+
+        write_metric(
+            &mut out,
+            "rota_requests_total",
+            "counter",
+            "Proxied requests handled",
+            self.requests(),
+        );
+
+        out.push_str("# HELP rota_responses_total Proxied requests by response status code\n");
+        out.push_str("# TYPE rota_responses_total counter\n");
+        for (status, count) in self.responses.lock().iter() {
+            let _ = writeln!(out, "rota_responses_total{{code=\"{}\"}} {}", status, count);
+        }
+
+        write_metric(
+            &mut out,
+            "rota_active_tunnels",
+            "gauge",
+            "CONNECT tunnels currently open",
+            self.active_tunnels(),
+        );
+        write_metric(
+            &mut out,
+            "rota_available_proxies",
+            "gauge",
+            "Proxies the selector can currently pick from",
+            available_proxies as u64,
+        );
+        write_metric(
+            &mut out,
+            "rota_rate_limited_total",
+            "counter",
+            "Requests rejected by the rate limiter",
+            self.rate_limited(),
+        );
+        write_metric(
+            &mut out,
+            "rota_dropped_log_broadcasts_total",
+            "counter",
+            "Request records live log subscribers skipped by falling behind",
+            self.dropped_broadcasts(),
+        );
+        write_metric(
+            &mut out,
+            "rota_dropped_log_persists_total",
+            "counter",
+            "Request records that failed to be written to the database",
+            self.dropped_persists(),
+        );
+
+        out
+    }
+}
+
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Keeps a tunnel counted in `rota_active_tunnels` while alive
+pub struct ActiveTunnel(Arc<ProxyMetrics>);
+
+impl Drop for ActiveTunnel {
+    fn drop(&mut self) {
+        self.0.active_tunnels.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counts_responses_and_tunnels() {
+        let metrics = Arc::new(ProxyMetrics::new());
+        metrics.record_response(StatusCode::OK);
+        metrics.record_response(StatusCode::OK);
+        metrics.record_response(StatusCode::BAD_GATEWAY);
+        metrics.record_rate_limited();
+        let tunnel = metrics.tunnel_opened();
+
+        let text = metrics.render(3);
+        assert!(text.contains("rota_requests_total 3\n"));
+        assert!(text.contains("rota_responses_total{code=\"200\"} 2\n"));
+        assert!(text.contains("rota_responses_total{code=\"502\"} 1\n"));
+        assert!(text.contains("rota_active_tunnels 1\n"));
+        assert!(text.contains("rota_available_proxies 3\n"));
+        assert!(text.contains("rota_rate_limited_total 1\n"));
+
+        drop(tunnel);
+        assert_eq!(metrics.active_tunnels(), 0);
+    }
+}
@@ -18,6 +18,8 @@ pub struct ProxyAuth {
     username: String,
     /// Expected password
     password: String,
+    /// Realm sent in the challenge
+    realm: String,
 }
 
 impl ProxyAuth {
@@ -27,15 +29,23 @@ impl ProxyAuth {
             enabled,
             username,
             password,
+            realm: "Proxy".to_string(),
         }
     }
 
+    /// Use `realm` in the `Proxy-Authenticate` challenge (control characters are dropped)
+    pub fn with_realm(mut self, realm: &str) -> Self {
+        self.realm = realm.chars().filter(|c| !c.is_control()).collect();
+        self
+    }
+
     /// Create a disabled auth handler
     pub fn disabled() -> Self {
         Self {
             enabled: false,
             username: String::new(),
             password: String::new(),
+            realm: "Proxy".to_string(),
         }
     }
 
@@ -50,32 +60,7 @@ impl ProxyAuth {
             return Ok(());
         }
 
-        let auth_header = req
-            .headers()
-            .get(PROXY_AUTHORIZATION)
-            .ok_or(RotaError::AuthenticationFailed)?;
-
-        let auth_str = auth_header
-            .to_str()
-            .map_err(|_| RotaError::AuthenticationFailed)?;
-
-        // Parse "Basic <base64>"
-        if !auth_str.starts_with("Basic ") {
-            warn!("Invalid auth scheme, expected Basic");
-            return Err(RotaError::AuthenticationFailed);
-        }
-
-        let encoded = &auth_str[6..];
-        let decoded = BASE64
-            .decode(encoded)
-            .map_err(|_| RotaError::AuthenticationFailed)?;
-
-        let credentials =
-            String::from_utf8(decoded).map_err(|_| RotaError::AuthenticationFailed)?;
-
-        let (user, pass) = credentials
-            .split_once(':')
-            .ok_or(RotaError::AuthenticationFailed)?;
+        let (user, pass) = basic_credentials(req)?;
 
         if user == self.username && pass == self.password {
             debug!("Proxy authentication successful for user: {}", user);
@@ -86,19 +71,64 @@ impl ProxyAuth {
         }
     }
 
+    /// Username from the request's `Proxy-Authorization`, once its credentials are verified
+    ///
+    /// `None` when authentication is disabled, since any username would pass unchecked.
+    pub fn verified_username<T>(&self, req: &Request<T>) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let (user, pass) = basic_credentials(req).ok()?;
+        (user == self.username && pass == self.password).then_some(user)
+    }
+
     /// Create a 407 Proxy Authentication Required response
+    ///
+    /// 407 and `Proxy-Authenticate` (not 401/`WWW-Authenticate`) so clients answer with
+    /// `Proxy-Authorization` instead of origin credentials.
     pub fn challenge_response<T>(&self) -> Response<T>
     where
         T: Default,
     {
+        let realm = self.realm.replace('\\', "\\\\").replace('"', "\\\"");
         Response::builder()
             .status(StatusCode::PROXY_AUTHENTICATION_REQUIRED)
-            .header(PROXY_AUTHENTICATE, "Basic realm=\"Proxy\"")
+            .header(PROXY_AUTHENTICATE, format!("Basic realm=\"{}\"", realm))
             .body(T::default())
             .unwrap()
     }
 }
 
+/// Username and password from a `Proxy-Authorization: Basic` header
+fn basic_credentials<T>(req: &Request<T>) -> Result<(String, String)> {
+    let auth_header = req
+        .headers()
+        .get(PROXY_AUTHORIZATION)
+        .ok_or(RotaError::AuthenticationFailed)?;
+
+    let auth_str = auth_header
+        .to_str()
+        .map_err(|_| RotaError::AuthenticationFailed)?;
+
+    // Parse "Basic <base64>"
+    if !auth_str.starts_with("Basic ") {
+        warn!("Invalid auth scheme, expected Basic");
+        return Err(RotaError::AuthenticationFailed);
+    }
+
+    let encoded = &auth_str[6..];
+    let decoded = BASE64
+        .decode(encoded)
+        .map_err(|_| RotaError::AuthenticationFailed)?;
+
+    let credentials = String::from_utf8(decoded).map_err(|_| RotaError::AuthenticationFailed)?;
+
+    let (user, pass) = credentials
+        .split_once(':')
+        .ok_or(RotaError::AuthenticationFailed)?;
+    Ok((user.to_string(), pass.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,6 +181,44 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_verified_username_only_when_accepted() {
+        let header = format!("Basic {}", BASE64.encode(b"alice:secret"));
+        let req = create_request_with_auth(Some(&header));
+
+        assert_eq!(ProxyAuth::disabled().verified_username(&req), None);
+        let auth = ProxyAuth::new(true, "alice".to_string(), "secret".to_string());
+        assert_eq!(auth.verified_username(&req), Some("alice".to_string()));
+        assert_eq!(
+            auth.verified_username(&create_request_with_auth(None)),
+            None
+        );
+        let auth = ProxyAuth::new(true, "alice".to_string(), "other".to_string());
+        assert_eq!(auth.verified_username(&req), None);
+    }
+
+    #[test]
+    fn test_challenge_response_is_407_with_realm() {
+        let auth = ProxyAuth::new(true, "user".to_string(), "pass".to_string());
+        let response: Response<Full<Bytes>> = auth.challenge_response();
+        assert_eq!(response.status(), StatusCode::PROXY_AUTHENTICATION_REQUIRED);
+        assert_eq!(
+            response.headers().get(PROXY_AUTHENTICATE).unwrap(),
+            "Basic realm=\"Proxy\""
+        );
+        assert!(response
+            .headers()
+            .get(hyper::header::WWW_AUTHENTICATE)
+            .is_none());
+
+        let auth = auth.with_realm("rota \"edge\"");
+        let response: Response<Full<Bytes>> = auth.challenge_response();
+        assert_eq!(
+            response.headers().get(PROXY_AUTHENTICATE).unwrap(),
+            "Basic realm=\"rota \\\"edge\\\"\""
+        );
+    }
+
     #[test]
     fn test_auth_invalid_scheme() {
         let auth = ProxyAuth::new(true, "user".to_string(), "pass".to_string());
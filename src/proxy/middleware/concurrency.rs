@@ -0,0 +1,256 @@
+//! Global concurrency limiting with priority-ordered waiting
+//!
+//! When all permits are in use, waiting requests are served strictly by
+//! priority class, and in arrival order within a class.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+/// Priority class of a request waiting for a concurrency permit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// Parse a priority name, falling back to `Normal` for unknown values
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "high" => Priority::High,
+            "low" => Priority::Low,
+            _ => Priority::Normal,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+struct LimiterState {
+    available: usize,
+    /// Waiters per priority class, highest priority first
+    waiters: [VecDeque<oneshot::Sender<()>>; 3],
+}
+
+struct LimiterInner {
+    state: Mutex<LimiterState>,
+}
+
+impl LimiterInner {
+    /// Hand a released permit to the highest-priority live waiter, or return it to the pool
+    fn release(&self) {
+        let mut state = self.state.lock();
+        for queue in state.waiters.iter_mut() {
+            while let Some(waiter) = queue.pop_front() {
+                if waiter.send(()).is_ok() {
+                    return;
+                }
+            }
+        }
+        state.available += 1;
+    }
+}
+
+/// Caps the number of requests handled at once
+///
+/// Cloning shares the same permits.
+#[derive(Clone)]
+pub struct ConcurrencyLimiter {
+    inner: Option<Arc<LimiterInner>>,
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter allowing `max_concurrent` requests at once (0 = unlimited)
+    pub fn new(max_concurrent: usize) -> Self {
+        if max_concurrent == 0 {
+            return Self::unlimited();
+        }
+
+        Self {
+            inner: Some(Arc::new(LimiterInner {
+                state: Mutex::new(LimiterState {
+                    available: max_concurrent,
+                    waiters: Default::default(),
+                }),
+            })),
+        }
+    }
+
+    /// Create a limiter that never makes requests wait
+    pub fn unlimited() -> Self {
+        Self { inner: None }
+    }
+
+    /// Wait for a permit; higher-priority waiters are served first
+    pub async fn acquire(&self, priority: Priority) -> ConcurrencyPermit {
+        let Some(inner) = &self.inner else {
+            return ConcurrencyPermit { inner: None };
+        };
+
+        let rx = {
+            let mut state = inner.state.lock();
+            if state.available > 0 {
+                state.available -= 1;
+                return ConcurrencyPermit {
+                    inner: Some(inner.clone()),
+                };
+            }
+
+            let (tx, rx) = oneshot::channel();
+            state.waiters[priority.index()].push_back(tx);
+            rx
+        };
+
+        let mut waiter = Waiter {
+            rx: Some(rx),
+            inner: inner.clone(),
+        };
+        if let Some(rx) = waiter.rx.as_mut() {
+            // Senders are only dropped after a successful send.
+            let _ = rx.await;
+        }
+        waiter.rx = None;
+
+        ConcurrencyPermit {
+            inner: Some(inner.clone()),
+        }
+    }
+
+    /// Number of requests currently waiting for a permit
+    #[cfg(test)]
+    fn waiting(&self) -> usize {
+        self.inner.as_ref().map_or(0, |inner| {
+            inner
+                .state
+                .lock()
+                .waiters
+                .iter()
+                .map(|queue| queue.iter().filter(|w| !w.is_closed()).count())
+                .sum()
+        })
+    }
+}
+
+/// Pending wait for a permit; gives the permit back if the wait is cancelled after it was granted
+struct Waiter {
+    rx: Option<oneshot::Receiver<()>>,
+    inner: Arc<LimiterInner>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            rx.close();
+            if rx.try_recv().is_ok() {
+                self.inner.release();
+            }
+        }
+    }
+}
+
+/// Held while a request is being handled; releases its slot on drop
+pub struct ConcurrencyPermit {
+    inner: Option<Arc<LimiterInner>>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn wait_for_waiters(limiter: &ConcurrencyLimiter, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while limiter.waiting() < count {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_priority_parse() {
+        assert_eq!(Priority::parse("HIGH"), Priority::High);
+        assert_eq!(Priority::parse(" low "), Priority::Low);
+        assert_eq!(Priority::parse("normal"), Priority::Normal);
+        assert_eq!(Priority::parse("urgent"), Priority::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_acquires_before_low() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let held = limiter.acquire(Priority::Normal).await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut tasks = Vec::new();
+        for (name, priority) in [
+            ("low-1", Priority::Low),
+            ("low-2", Priority::Low),
+            ("normal", Priority::Normal),
+            ("high", Priority::High),
+        ] {
+            let task_limiter = limiter.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = task_limiter.acquire(priority).await;
+                order.lock().push(name);
+            }));
+            wait_for_waiters(&limiter, tasks.len()).await;
+        }
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(*order.lock(), vec!["high", "normal", "low-1", "low-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_leak_permit() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let held = limiter.acquire(Priority::Normal).await;
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                let _permit = limiter.acquire(Priority::High).await;
+            })
+        };
+        wait_for_waiters(&limiter, 1).await;
+        waiter.abort();
+        let _ = waiter.await;
+
+        drop(held);
+        let acquired =
+            tokio::time::timeout(Duration::from_secs(1), limiter.acquire(Priority::Low)).await;
+        assert!(acquired.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_never_waits() {
+        let limiter = ConcurrencyLimiter::new(0);
+        let _a = limiter.acquire(Priority::Low).await;
+        let _b = limiter.acquire(Priority::Low).await;
+        assert_eq!(limiter.waiting(), 0);
+    }
+}
@@ -0,0 +1,188 @@
+//! Denylist middleware for the proxy server
+//!
+//! Rejects requests from denied client IPs or to denied target hosts, either with a 403
+//! or by dropping the connection so the client can't tell it was filtered.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::header::HOST;
+use hyper::Request;
+
+use crate::config::DenylistConfig;
+
+/// What to do with a request that matches the denylist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DenyAction {
+    /// Respond 403 Forbidden
+    #[default]
+    Forbid,
+    /// Close the connection without sending a response
+    Close,
+    /// Keep the connection open for the hold period, then close it without a response
+    Hold,
+}
+
+impl DenyAction {
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "close" | "drop" | "reset" => Self::Close,
+            "hold" | "tarpit" => Self::Hold,
+            _ => Self::Forbid,
+        }
+    }
+}
+
+/// Client IP and target host denylist
+#[derive(Clone, Default)]
+pub struct Denylist {
+    /// Denied target hosts (lowercase); each also denies its subdomains
+    hosts: Arc<Vec<String>>,
+    /// Denied client IPs
+    ips: Arc<Vec<IpAddr>>,
+    action: DenyAction,
+    hold: Duration,
+}
+
+impl Denylist {
+    /// Create a denylist; entries in `ips` that aren't IP addresses are ignored
+    pub fn new(hosts: &[String], ips: &[String], action: DenyAction, hold: Duration) -> Self {
+        Self {
+            hosts: Arc::new(
+                hosts
+                    .iter()
+                    .map(|h| h.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|h| !h.is_empty())
+                    .collect(),
+            ),
+            ips: Arc::new(ips.iter().filter_map(|ip| ip.trim().parse().ok()).collect()),
+            action,
+            hold,
+        }
+    }
+
+    pub fn from_config(config: &DenylistConfig) -> Self {
+        Self::new(
+            &config.hosts,
+            &config.ips,
+            DenyAction::parse(&config.action),
+            Duration::from_secs(config.hold_seconds),
+        )
+    }
+
+    /// Create an empty denylist that matches nothing
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn action(&self) -> DenyAction {
+        self.action
+    }
+
+    /// How long a `Hold` action keeps the connection open
+    pub fn hold(&self) -> Duration {
+        self.hold
+    }
+
+    /// Whether a request from `client_ip` must be rejected
+    pub fn is_denied<T>(&self, client_ip: &str, req: &Request<T>) -> bool {
+        if self.hosts.is_empty() && self.ips.is_empty() {
+            return false;
+        }
+
+        self.is_denied_ip(client_ip) || target_host(req).is_some_and(|h| self.is_denied_host(&h))
+    }
+
+    fn is_denied_ip(&self, client_ip: &str) -> bool {
+        client_ip
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| self.ips.contains(&ip))
+    }
+
+    fn is_denied_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+        self.hosts.iter().any(|denied| {
+            host == *denied
+                || host
+                    .strip_suffix(denied.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+/// Target host of a proxy request: the URI authority, else the `Host` header
+fn target_host<T>(req: &Request<T>) -> Option<String> {
+    if let Some(host) = req.uri().host() {
+        return Some(host.to_string());
+    }
+
+    let host = req.headers().get(HOST)?.to_str().ok()?;
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if port.parse::<u16>().is_ok() => name,
+        _ => host,
+    };
+    Some(
+        host.trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn denylist(hosts: &[&str], ips: &[&str]) -> Denylist {
+        let hosts: Vec<String> = hosts.iter().map(|s| s.to_string()).collect();
+        let ips: Vec<String> = ips.iter().map(|s| s.to_string()).collect();
+        Denylist::new(&hosts, &ips, DenyAction::Forbid, Duration::ZERO)
+    }
+
+    fn request(uri: &str) -> Request<()> {
+        Request::builder().uri(uri).body(()).unwrap()
+    }
+
+    #[test]
+    fn test_deny_action_parse() {
+        assert_eq!(DenyAction::parse("close"), DenyAction::Close);
+        assert_eq!(DenyAction::parse("RESET"), DenyAction::Close);
+        assert_eq!(DenyAction::parse("hold"), DenyAction::Hold);
+        assert_eq!(DenyAction::parse("forbid"), DenyAction::Forbid);
+        assert_eq!(DenyAction::parse("unknown"), DenyAction::Forbid);
+    }
+
+    #[test]
+    fn test_denies_hosts_and_subdomains() {
+        let list = denylist(&["Blocked.example"], &[]);
+
+        assert!(list.is_denied("10.0.0.1", &request("http://blocked.example/")));
+        assert!(list.is_denied("10.0.0.1", &request("http://api.blocked.example/x")));
+        assert!(list.is_denied("10.0.0.1", &request("blocked.example:443")));
+        assert!(!list.is_denied("10.0.0.1", &request("http://notblocked.example/")));
+        assert!(!list.is_denied("10.0.0.1", &request("http://example.com/")));
+
+        let origin_form = Request::builder()
+            .uri("/path")
+            .header(HOST, "blocked.example:8080")
+            .body(())
+            .unwrap();
+        assert!(list.is_denied("10.0.0.1", &origin_form));
+    }
+
+    #[test]
+    fn test_denies_client_ips() {
+        let list = denylist(&[], &["10.0.0.9", "::1", "not-an-ip"]);
+
+        assert!(list.is_denied("10.0.0.9", &request("http://example.com/")));
+        assert!(list.is_denied("::1", &request("http://example.com/")));
+        assert!(!list.is_denied("10.0.0.1", &request("http://example.com/")));
+    }
+
+    #[test]
+    fn test_disabled_denies_nothing() {
+        let list = Denylist::disabled();
+        assert_eq!(list.action(), DenyAction::Forbid);
+        assert!(!list.is_denied("10.0.0.9", &request("http://example.com/")));
+    }
+}
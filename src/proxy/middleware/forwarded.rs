@@ -0,0 +1,164 @@
+//! Client IP resolution behind trusted load balancers
+//!
+//! Connections from a trusted proxy carry the real client in `X-Forwarded-For` (or another
+//! configured header such as `X-Real-IP`). Each hop appends the address it saw, so the list
+//! is read from the right and the first entry outside the trusted ranges is the client;
+//! anything further left could be forged.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use hyper::header::HeaderName;
+use hyper::HeaderMap;
+use ipnet::IpNet;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Address ranges whose client IP headers are believed
+#[derive(Clone)]
+pub struct TrustedProxies {
+    ranges: Arc<Vec<IpNet>>,
+    header: HeaderName,
+}
+
+impl Default for TrustedProxies {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl TrustedProxies {
+    pub fn new(ranges: Vec<IpNet>) -> Self {
+        Self {
+            ranges: Arc::new(ranges),
+            header: HeaderName::from_static(X_FORWARDED_FOR),
+        }
+    }
+
+    /// Read the client from `header` instead of `X-Forwarded-For`
+    pub fn with_header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(&ip))
+    }
+
+    /// The client a request came from
+    ///
+    /// `peer` is the TCP peer. Unless it is trusted, or when the header holds no usable
+    /// entry, the peer itself is the client.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        let entries: Vec<&str> = headers
+            .get_all(&self.header)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .collect();
+
+        let mut client = peer;
+        for entry in entries.iter().rev() {
+            let Ok(ip) = entry.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trusted() -> TrustedProxies {
+        TrustedProxies::new(vec![
+            "10.0.0.0/8".parse().unwrap(),
+            "fd00::/8".parse().unwrap(),
+        ])
+    }
+
+    fn headers(forwarded_for: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in forwarded_for {
+            headers.append(X_FORWARDED_FOR, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_untrusted_peer_cannot_spoof_client() {
+        let resolved =
+            trusted().client_ip(ip("203.0.113.9"), &headers(&["198.51.100.1, 10.0.0.2"]));
+        assert_eq!(resolved, ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn test_trusted_peer_uses_rightmost_untrusted_entry() {
+        // The client forged the first entry; the balancer appended the address it saw
+        let resolved = trusted().client_ip(
+            ip("10.0.0.1"),
+            &headers(&["198.51.100.1, 203.0.113.9", "10.1.2.3"]),
+        );
+        assert_eq!(resolved, ip("203.0.113.9"));
+
+        let resolved = trusted().client_ip(ip("fd00::1"), &headers(&["2001:db8::7"]));
+        assert_eq!(resolved, ip("2001:db8::7"));
+    }
+
+    #[test]
+    fn test_trusted_peer_falls_back_to_peer() {
+        let trusted = trusted();
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &headers(&[])),
+            ip("10.0.0.1")
+        );
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &headers(&["not-an-ip"])),
+            ip("10.0.0.1")
+        );
+        // Every hop trusted: the leftmost is as far back as the chain goes
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &headers(&["10.9.9.9, 10.0.0.5"])),
+            ip("10.9.9.9")
+        );
+    }
+
+    #[test]
+    fn test_no_trusted_ranges_ignores_header() {
+        let resolved =
+            TrustedProxies::default().client_ip(ip("10.0.0.1"), &headers(&["203.0.113.9"]));
+        assert_eq!(resolved, ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn test_configured_header_is_used_only_from_trusted_peers() {
+        let trusted = trusted().with_header(HeaderName::from_static("x-real-ip"));
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", "203.0.113.9".parse().unwrap());
+        headers.insert(X_FORWARDED_FOR, "198.51.100.1".parse().unwrap());
+
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &headers),
+            ip("203.0.113.9")
+        );
+        assert_eq!(
+            trusted.client_ip(ip("192.0.2.50"), &headers),
+            ip("192.0.2.50")
+        );
+    }
+}
@@ -1,7 +1,13 @@
-//! Proxy middleware for authentication and rate limiting
+//! Proxy middleware for authentication, denylisting, rate limiting and concurrency limiting
 
 mod auth;
+mod concurrency;
+mod denylist;
+mod forwarded;
 mod rate_limit;
 
 pub use auth::ProxyAuth;
+pub use concurrency::{ConcurrencyLimiter, Priority};
+pub use denylist::{DenyAction, Denylist};
+pub use forwarded::TrustedProxies;
 pub use rate_limit::RateLimiter;
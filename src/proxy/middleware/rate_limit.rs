@@ -3,23 +3,29 @@
 //! Uses the governor crate for efficient, lock-free rate limiting.
 
 use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use dashmap::DashMap;
 use governor::clock::DefaultClock;
 use governor::state::{InMemoryState, NotKeyed};
 use governor::{Quota, RateLimiter as GovRateLimiter};
-use tracing::{debug, warn};
+use serde::Serialize;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
 
 use crate::error::{Result, RotaError};
-use crate::models::RateLimitSettings;
+use crate::models::{RateLimitKey, RateLimitSettings, Settings};
+use crate::proxy::metrics::ProxyMetrics;
 
 #[derive(Debug)]
 struct ClientLimiter {
     limiter: Arc<GovRateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
     last_seen_ms: std::sync::atomic::AtomicU64,
+    blocked: AtomicU64,
+    would_block: AtomicU64,
 }
 
 impl ClientLimiter {
@@ -30,10 +36,22 @@ impl ClientLimiter {
         Self {
             limiter,
             last_seen_ms: std::sync::atomic::AtomicU64::new(now_ms),
+            blocked: AtomicU64::new(0),
+            would_block: AtomicU64::new(0),
         }
     }
 }
 
+/// Throttling counters for a single client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ThrottledClient {
+    pub client: String,
+    /// Requests rejected by the rate limiter
+    pub blocked: u64,
+    /// Requests that exceeded the quota while in observe-only mode
+    pub would_block: u64,
+}
+
 fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -41,11 +59,41 @@ fn now_ms() -> u64 {
         .as_millis() as u64
 }
 
+/// Quota allowing `max_requests` over `interval_secs`, with burst == max_requests
+///
+/// Both are clamped to at least 1.
+fn quota_for(interval_secs: i32, max_requests: i32) -> Quota {
+    let interval_secs = interval_secs.max(1) as u64;
+    let max_requests = max_requests.max(1) as u32;
+    let max_burst = NonZeroU32::new(max_requests).unwrap_or_else(|| NonZeroU32::new(1).unwrap());
+
+    let mut replenish_1_per = Duration::from_secs(interval_secs) / max_burst.get();
+    if replenish_1_per.is_zero() {
+        replenish_1_per = Duration::from_nanos(1);
+    }
+
+    Quota::with_period(replenish_1_per)
+        .unwrap_or_else(|| Quota::per_second(NonZeroU32::new(1).unwrap()))
+        .allow_burst(max_burst)
+}
+
+/// Clients kept when evicting for `max_clients`: a tenth fewer, so a flood of new clients
+/// doesn't trigger an eviction on every request
+fn eviction_target(max_clients: usize) -> usize {
+    max_clients - (max_clients / 10).max(1)
+}
+
 #[derive(Clone, Copy)]
 struct RateLimiterConfig {
     enabled: bool,
+    observe_only: bool,
     quota: Quota,
+    /// Quota shared by all clients; `None` when the global limit is off
+    global_quota: Option<Quota>,
     max_idle: Duration,
+    /// Most clients tracked at once (0 = no cap)
+    max_clients: usize,
+    key: RateLimitKey,
 }
 
 /// Rate limiter for proxy requests
@@ -53,6 +101,11 @@ pub struct RateLimiter {
     config: Arc<ArcSwap<RateLimiterConfig>>,
     /// Rate limiters per client IP
     limiters: Arc<DashMap<String, ClientLimiter>>,
+    /// Limiter shared by all clients, checked before the per-client ones
+    global: Arc<ArcSwapOption<GovRateLimiter<NotKeyed, InMemoryState, DefaultClock>>>,
+    /// Requests that exceeded the quota while in observe-only mode
+    would_block: Arc<AtomicU64>,
+    metrics: Arc<ProxyMetrics>,
 }
 
 impl RateLimiter {
@@ -64,13 +117,26 @@ impl RateLimiter {
         Self {
             config: Arc::new(ArcSwap::from_pointee(RateLimiterConfig {
                 enabled,
+                observe_only: false,
                 quota: Quota::per_second(requests_per_second).allow_burst(burst_size),
+                global_quota: None,
                 max_idle: Duration::from_secs(10 * 60),
+                max_clients: 0,
+                key: RateLimitKey::ClientIp,
             })),
             limiters: Arc::new(DashMap::new()),
+            global: Arc::new(ArcSwapOption::empty()),
+            would_block: Arc::new(AtomicU64::new(0)),
+            metrics: Arc::new(ProxyMetrics::new()),
         }
     }
 
+    /// Count rejected requests in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<ProxyMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     /// Create a disabled rate limiter
     pub fn disabled() -> Self {
         Self::new(false, 100, 100)
@@ -81,49 +147,125 @@ impl RateLimiter {
         self.config.load().enabled
     }
 
-    pub fn apply_settings(&self, settings: &RateLimitSettings) {
-        let enabled = settings.enabled;
+    /// Check if rate limiting only observes instead of rejecting
+    pub fn is_observe_only(&self) -> bool {
+        self.config.load().observe_only
+    }
 
-        let interval_secs = settings.interval.max(1) as u64;
-        let max_requests = settings.max_requests.max(1) as u32;
-        let max_burst =
-            NonZeroU32::new(max_requests).unwrap_or_else(|| NonZeroU32::new(1).unwrap());
+    /// What clients are told apart by
+    pub fn key(&self) -> RateLimitKey {
+        self.config.load().key
+    }
 
-        let mut replenish_1_per = Duration::from_secs(interval_secs) / max_burst.get();
-        if replenish_1_per.is_zero() {
-            replenish_1_per = Duration::from_nanos(1);
+    /// Client key for a request from `client_ip` that authenticated as `username`
+    ///
+    /// Usernames are prefixed so they can't collide with IP addresses.
+    pub fn client_key(&self, client_ip: &str, username: Option<&str>) -> String {
+        match (self.key(), username) {
+            (RateLimitKey::AuthUser, Some(username)) => format!("user:{}", username),
+            _ => client_ip.to_string(),
         }
+    }
 
-        // Quota: allow `max_requests` over `interval` seconds, with burst == max_requests.
-        let quota = Quota::with_period(replenish_1_per)
-            .unwrap_or_else(|| Quota::per_second(NonZeroU32::new(1).unwrap()))
-            .allow_burst(max_burst);
+    /// Number of requests that would have been blocked in observe-only mode
+    pub fn would_block_count(&self) -> u64 {
+        self.would_block.load(Ordering::Relaxed)
+    }
+
+    pub fn apply_settings(&self, settings: &RateLimitSettings) {
+        let enabled = settings.enabled;
+        let quota = quota_for(settings.interval, settings.max_requests);
+        let global_quota = settings
+            .global_enabled
+            .then(|| quota_for(settings.interval, settings.global_max_requests));
 
-        let max_idle = self.config.load().max_idle;
+        let current = self.config.load();
+        let max_idle = current.max_idle;
 
-        // Clear per-client state so new quota applies immediately.
-        self.limiters.clear();
+        // Clear per-client state so a new quota applies immediately; other changes keep it.
+        if current.quota != quota {
+            self.limiters.clear();
+        }
+        if current.global_quota != global_quota {
+            self.global
+                .store(global_quota.map(|quota| Arc::new(GovRateLimiter::direct(quota))));
+        }
         self.config.store(Arc::new(RateLimiterConfig {
             enabled,
+            observe_only: settings.observe_only,
             quota,
+            global_quota,
             max_idle,
+            max_clients: usize::try_from(settings.max_clients).unwrap_or(0),
+            key: settings.key,
         }));
+        self.enforce_max_clients();
+    }
+
+    /// Apply rate limit settings published on `settings_rx` until `shutdown`
+    pub async fn watch_settings(
+        &self,
+        mut settings_rx: watch::Receiver<Settings>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        loop {
+            tokio::select! {
+                changed = settings_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let rate_limit = settings_rx.borrow_and_update().rate_limit.clone();
+                    self.apply_settings(&rate_limit);
+                }
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
     }
 
-    /// Check if a request from the given client IP is allowed
+    /// Check if a request from the given client key (see [`Self::client_key`]) is allowed
+    ///
+    /// The global limit, when set, is checked first; requests it rejects report `*` as
+    /// the client.
     pub fn check(&self, client_ip: &str) -> Result<()> {
-        if !self.config.load().enabled {
+        let config = self.config.load();
+        if let Some(global) = self.global.load().as_ref() {
+            if global.check().is_err() {
+                if config.observe_only {
+                    self.would_block.fetch_add(1, Ordering::Relaxed);
+                    info!("Global rate limit would block {} (observe-only)", client_ip);
+                } else {
+                    self.metrics.record_rate_limited();
+                    warn!("Global rate limit exceeded, rejecting {}", client_ip);
+                    return Err(RotaError::RateLimitExceeded {
+                        client_ip: "*".to_string(),
+                    });
+                }
+            }
+        }
+        if !config.enabled {
             return Ok(());
         }
 
-        let limiter = self.get_or_create_limiter(client_ip);
+        let entry = self.get_or_create_limiter(client_ip);
 
-        match limiter.check() {
+        match entry.limiter.check() {
             Ok(_) => {
                 debug!("Rate limit check passed for {}", client_ip);
                 Ok(())
             }
+            Err(_) if config.observe_only => {
+                self.would_block.fetch_add(1, Ordering::Relaxed);
+                entry.would_block.fetch_add(1, Ordering::Relaxed);
+                info!("Rate limit would block {} (observe-only)", client_ip);
+                Ok(())
+            }
             Err(_) => {
+                entry.blocked.fetch_add(1, Ordering::Relaxed);
+                self.metrics.record_rate_limited();
                 warn!("Rate limit exceeded for {}", client_ip);
                 Err(RotaError::RateLimitExceeded {
                     client_ip: client_ip.to_string(),
@@ -132,14 +274,26 @@ impl RateLimiter {
         }
     }
 
-    /// Get or create a rate limiter for the given client IP
+    /// Get or create the limiter entry for the given client IP
+    ///
+    /// Tracking a new client first makes room for it when the map is at `max_clients`.
     fn get_or_create_limiter(
         &self,
         client_ip: &str,
-    ) -> Arc<GovRateLimiter<NotKeyed, InMemoryState, DefaultClock>> {
+    ) -> dashmap::mapref::one::RefMut<'_, String, ClientLimiter> {
         let now_ms = now_ms();
-        let quota = self.config.load().quota;
+        let config = self.config.load();
+
+        if let Some(entry) = self.limiters.get_mut(client_ip) {
+            entry.last_seen_ms.store(now_ms, Ordering::Relaxed);
+            return entry;
+        }
+
+        if config.max_clients > 0 && self.limiters.len() >= config.max_clients {
+            self.evict_least_recent(eviction_target(config.max_clients));
+        }
 
+        let quota = config.quota;
         let entry = self
             .limiters
             .entry(client_ip.to_string())
@@ -149,10 +303,13 @@ impl RateLimiter {
             .last_seen_ms
             .store(now_ms, std::sync::atomic::Ordering::Relaxed);
 
-        entry.limiter.clone()
+        entry
     }
 
     /// Clean up old rate limiters (call periodically)
+    ///
+    /// Evicts clients idle for longer than `max_idle`, then the least recently seen ones
+    /// while more than `max_clients` remain.
     pub fn cleanup(&self) {
         let now_ms = now_ms();
         let max_idle_ms = self.config.load().max_idle.as_millis() as u64;
@@ -163,6 +320,61 @@ impl RateLimiter {
                 .load(std::sync::atomic::Ordering::Relaxed);
             now_ms.saturating_sub(last_seen) <= max_idle_ms
         });
+        self.enforce_max_clients();
+    }
+
+    /// Evict down to the eviction target when more than `max_clients` are tracked
+    fn enforce_max_clients(&self) {
+        let max_clients = self.config.load().max_clients;
+        if max_clients > 0 && self.limiters.len() > max_clients {
+            self.evict_least_recent(eviction_target(max_clients));
+        }
+    }
+
+    /// Evict the least recently seen clients until at most `target` remain
+    fn evict_least_recent(&self, target: usize) {
+        let mut seen: Vec<(String, u64)> = self
+            .limiters
+            .iter()
+            .map(|entry| {
+                (
+                    entry.key().clone(),
+                    entry.last_seen_ms.load(Ordering::Relaxed),
+                )
+            })
+            .collect();
+        if seen.len() <= target {
+            return;
+        }
+
+        seen.sort_unstable_by_key(|(_, last_seen)| *last_seen);
+        let excess = seen.len() - target;
+        for (client, _) in seen.into_iter().take(excess) {
+            self.limiters.remove(&client);
+        }
+        debug!("Evicted {} rate-limited clients over the cap", excess);
+    }
+
+    /// Clients with the most blocked (or would-block) requests, most throttled first
+    pub fn top_throttled(&self, limit: usize) -> Vec<ThrottledClient> {
+        let mut clients: Vec<ThrottledClient> = self
+            .limiters
+            .iter()
+            .map(|entry| ThrottledClient {
+                client: entry.key().clone(),
+                blocked: entry.blocked.load(Ordering::Relaxed),
+                would_block: entry.would_block.load(Ordering::Relaxed),
+            })
+            .filter(|c| c.blocked > 0 || c.would_block > 0)
+            .collect();
+
+        clients.sort_by(|a, b| {
+            (b.blocked + b.would_block)
+                .cmp(&(a.blocked + a.would_block))
+                .then_with(|| a.client.cmp(&b.client))
+        });
+        clients.truncate(limit);
+        clients
     }
 
     /// Get the number of tracked clients
@@ -176,6 +388,9 @@ impl Clone for RateLimiter {
         Self {
             config: Arc::clone(&self.config),
             limiters: Arc::clone(&self.limiters),
+            global: Arc::clone(&self.global),
+            would_block: Arc::clone(&self.would_block),
+            metrics: Arc::clone(&self.metrics),
         }
     }
 }
@@ -222,6 +437,17 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_rate_limiter_counts_rejections_in_metrics() {
+        let metrics = Arc::new(ProxyMetrics::new());
+        let limiter = RateLimiter::new(true, 1, 1).with_metrics(metrics.clone());
+
+        assert!(limiter.check("192.168.1.1").is_ok());
+        assert!(limiter.check("192.168.1.1").is_err());
+        assert!(limiter.clone().check("192.168.1.1").is_err());
+        assert_eq!(metrics.rate_limited(), 2);
+    }
+
     #[test]
     fn test_rate_limiter_per_ip() {
         let limiter = RateLimiter::new(true, 1, 1);
@@ -257,6 +483,8 @@ mod tests {
             enabled: true,
             interval: 60,
             max_requests: 2,
+            observe_only: false,
+            ..Default::default()
         });
 
         limiter.check("192.168.1.1").ok();
@@ -267,12 +495,70 @@ mod tests {
             enabled: true,
             interval: 60,
             max_requests: 100,
+            observe_only: false,
+            ..Default::default()
         });
 
         assert_eq!(limiter.client_count(), 0);
         assert!(limiter.check("192.168.1.1").is_ok());
     }
 
+    #[test]
+    fn test_apply_settings_keeps_client_state_for_same_quota() {
+        let limiter = RateLimiter::disabled();
+        let mut settings = RateLimitSettings {
+            enabled: true,
+            interval: 60,
+            max_requests: 1,
+            observe_only: false,
+            ..Default::default()
+        };
+        limiter.apply_settings(&settings);
+        limiter.check("192.168.1.1").ok();
+
+        settings.observe_only = true;
+        limiter.apply_settings(&settings);
+        assert_eq!(limiter.client_count(), 1);
+        // The client's bucket is still empty
+        limiter.check("192.168.1.1").ok();
+        assert_eq!(limiter.would_block_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_settings_updates_apply_without_restart() {
+        let limiter = Arc::new(RateLimiter::disabled());
+        let (settings_tx, settings_rx) = watch::channel(Settings::default());
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let watcher = limiter.clone();
+        let task =
+            tokio::spawn(async move { watcher.watch_settings(settings_rx, shutdown_rx).await });
+
+        let wait_for = |enabled: bool| {
+            let limiter = limiter.clone();
+            async move {
+                tokio::time::timeout(Duration::from_secs(5), async {
+                    while limiter.is_enabled() != enabled {
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                    }
+                })
+                .await
+                .expect("rate limit settings were never applied");
+            }
+        };
+
+        let mut settings = Settings::default();
+        settings.rate_limit.enabled = true;
+        settings_tx.send(settings.clone()).unwrap();
+        wait_for(true).await;
+
+        settings.rate_limit.enabled = false;
+        settings_tx.send(settings).unwrap();
+        wait_for(false).await;
+
+        shutdown_tx.send(true).unwrap();
+        task.await.unwrap();
+    }
+
     #[test]
     fn test_apply_settings_uses_clamped_values() {
         let limiter = RateLimiter::disabled();
@@ -281,6 +567,8 @@ mod tests {
             enabled: true,
             interval: 0,
             max_requests: 0,
+            observe_only: false,
+            ..Default::default()
         });
 
         // Clamped to 1 request per 1 second.
@@ -290,4 +578,114 @@ mod tests {
             Err(RotaError::RateLimitExceeded { .. })
         ));
     }
+
+    #[test]
+    fn test_observe_only_counts_would_blocks() {
+        let limiter = RateLimiter::disabled();
+
+        limiter.apply_settings(&RateLimitSettings {
+            enabled: true,
+            interval: 60,
+            max_requests: 2,
+            observe_only: true,
+            ..Default::default()
+        });
+        assert!(limiter.is_observe_only());
+
+        for _ in 0..5 {
+            assert!(limiter.check("192.168.1.1").is_ok());
+        }
+        assert_eq!(limiter.would_block_count(), 3);
+    }
+
+    #[test]
+    fn test_global_limit_blocks_distinct_clients() {
+        let limiter = RateLimiter::disabled();
+        let mut settings = RateLimitSettings {
+            enabled: false,
+            interval: 60,
+            max_requests: 100,
+            observe_only: false,
+            global_enabled: true,
+            global_max_requests: 3,
+            max_clients: 100_000,
+            key: RateLimitKey::ClientIp,
+        };
+        limiter.apply_settings(&settings);
+
+        for i in 1..=3 {
+            assert!(limiter.check(&format!("192.168.1.{}", i)).is_ok());
+        }
+        match limiter.check("192.168.1.4") {
+            Err(RotaError::RateLimitExceeded { client_ip }) => assert_eq!(client_ip, "*"),
+            other => panic!("expected the global limit to trip, got {:?}", other),
+        }
+        // Distinct clients are not tracked for a global rejection
+        assert_eq!(limiter.client_count(), 0);
+
+        // Re-applying the same global quota keeps its state
+        settings.enabled = true;
+        limiter.apply_settings(&settings);
+        assert!(limiter.check("192.168.1.5").is_err());
+
+        settings.global_enabled = false;
+        limiter.apply_settings(&settings);
+        assert!(limiter.check("192.168.1.5").is_ok());
+    }
+
+    #[test]
+    fn test_client_map_stays_within_max_clients() {
+        let limiter = RateLimiter::disabled();
+        limiter.apply_settings(&RateLimitSettings {
+            enabled: true,
+            max_clients: 20,
+            ..Default::default()
+        });
+
+        for i in 0..500 {
+            limiter.check(&format!("10.0.{}.{}", i / 256, i % 256)).ok();
+            assert!(limiter.client_count() <= 20);
+        }
+
+        // The newest client is never the one evicted to make room
+        assert!(limiter.limiters.contains_key("10.0.1.243"));
+
+        // Lowering the cap applies right away
+        limiter.apply_settings(&RateLimitSettings {
+            enabled: true,
+            max_clients: 5,
+            ..Default::default()
+        });
+        assert!(limiter.client_count() <= 5);
+    }
+
+    #[test]
+    fn test_blocks_increment_per_client_counter() {
+        let limiter = RateLimiter::new(true, 1, 1);
+
+        for _ in 0..4 {
+            limiter.check("192.168.1.1").ok();
+        }
+        for _ in 0..2 {
+            limiter.check("192.168.1.2").ok();
+        }
+        limiter.check("192.168.1.3").ok();
+
+        assert_eq!(
+            limiter.top_throttled(10),
+            vec![
+                ThrottledClient {
+                    client: "192.168.1.1".to_string(),
+                    blocked: 3,
+                    would_block: 0,
+                },
+                ThrottledClient {
+                    client: "192.168.1.2".to_string(),
+                    blocked: 1,
+                    would_block: 0,
+                },
+            ]
+        );
+        assert_eq!(limiter.top_throttled(1).len(), 1);
+    }
 }
@@ -5,16 +5,24 @@
 //! - CONNECT tunnel for HTTPS
 //! - Multiple proxy rotation strategies
 //! - Health checking
+//! - Prometheus traffic metrics
+//! - Warm pool of pre-dialed upstream proxy connections
+//! - Keep-alive reuse of HTTP/1.1 connections to upstream proxies
 //! - Request/response handling with retry logic
+//! - Custom bodies for rejected requests
 
 pub mod egress;
 pub mod handler;
 pub mod health;
+pub mod metrics;
 pub mod middleware;
+pub mod responses;
 pub mod rotation;
 pub mod server;
 pub mod transport;
 pub mod tunnel;
+pub mod upstream_pool;
+pub mod warm_pool;
 
 pub use handler::ProxyHandler;
 pub use health::HealthChecker;
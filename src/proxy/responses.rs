@@ -0,0 +1,199 @@
+//! Custom bodies for responses the proxy rejects requests with
+//!
+//! Operators running Rota as a user-facing gateway can replace the plain-text messages
+//! of rate-limited, unavailable and all-failed responses with their own pages. The
+//! templates come from the `responses` settings and are swapped in whenever those change.
+
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use hyper::StatusCode;
+use tokio::sync::watch;
+use tracing::warn;
+
+use crate::models::{ResponseSettings, ResponseTemplate, Settings};
+
+const DEFAULT_CONTENT_TYPE: &str = "text/plain";
+
+/// Rejection whose response body can be customized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    RateLimited,
+    Unavailable,
+    AllFailed,
+}
+
+/// A template ready to render, with any file already read
+#[derive(Debug, Clone)]
+struct LoadedTemplate {
+    content_type: String,
+    body: String,
+}
+
+#[derive(Debug, Default)]
+struct Loaded {
+    rate_limited: Option<LoadedTemplate>,
+    unavailable: Option<LoadedTemplate>,
+    all_failed: Option<LoadedTemplate>,
+}
+
+impl Loaded {
+    fn get(&self, rejection: Rejection) -> Option<&LoadedTemplate> {
+        match rejection {
+            Rejection::RateLimited => self.rate_limited.as_ref(),
+            Rejection::Unavailable => self.unavailable.as_ref(),
+            Rejection::AllFailed => self.all_failed.as_ref(),
+        }
+    }
+}
+
+/// Current response templates, shared by every connection
+#[derive(Clone, Default)]
+pub struct ResponseTemplates {
+    loaded: Arc<ArcSwap<Loaded>>,
+}
+
+impl ResponseTemplates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the templates with those in `settings`
+    ///
+    /// A template whose file can't be read is left out, so its built-in message is used.
+    pub fn apply_settings(&self, settings: &ResponseSettings) {
+        self.loaded.store(Arc::new(Loaded {
+            rate_limited: load(settings.rate_limited.as_ref()),
+            unavailable: load(settings.unavailable.as_ref()),
+            all_failed: load(settings.all_failed.as_ref()),
+        }));
+    }
+
+    /// Apply response settings published on `settings_rx` until `shutdown`
+    pub async fn watch_settings(
+        &self,
+        mut settings_rx: watch::Receiver<Settings>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        loop {
+            tokio::select! {
+                changed = settings_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let responses = settings_rx.borrow_and_update().responses.clone();
+                    self.apply_settings(&responses);
+                }
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Content type and body for `rejection`, or `None` to use `message` as is
+    pub fn render(
+        &self,
+        rejection: Rejection,
+        status: StatusCode,
+        message: &str,
+    ) -> Option<(String, String)> {
+        let loaded = self.loaded.load();
+        let template = loaded.get(rejection)?;
+        let body = template
+            .body
+            .replace("{{status}}", status.as_str())
+            .replace("{{message}}", message);
+        Some((template.content_type.clone(), body))
+    }
+}
+
+fn load(template: Option<&ResponseTemplate>) -> Option<LoadedTemplate> {
+    let template = template?;
+    let body = match template.file.as_deref().filter(|f| !f.is_empty()) {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to read response template {}: {}", path, e);
+                return None;
+            }
+        },
+        None => template.body.clone(),
+    };
+    let content_type = if template.content_type.is_empty() {
+        DEFAULT_CONTENT_TYPE.to_string()
+    } else {
+        template.content_type.clone()
+    };
+    Some(LoadedTemplate { content_type, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_render_and_fall_back() {
+        let templates = ResponseTemplates::new();
+        assert!(templates
+            .render(
+                Rejection::RateLimited,
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit exceeded"
+            )
+            .is_none());
+
+        let path = std::env::temp_dir().join(format!("rota-503-{}.html", std::process::id()));
+        std::fs::write(&path, "<h1>{{status}}: back soon</h1>").unwrap();
+        templates.apply_settings(&ResponseSettings {
+            rate_limited: Some(ResponseTemplate {
+                content_type: "application/json".to_string(),
+                body: r#"{"error":"{{message}}"}"#.to_string(),
+                file: None,
+            }),
+            unavailable: Some(ResponseTemplate {
+                content_type: "text/html".to_string(),
+                body: String::new(),
+                file: Some(path.to_string_lossy().into_owned()),
+            }),
+            all_failed: Some(ResponseTemplate {
+                file: Some("/nonexistent/rota-template".to_string()),
+                ..Default::default()
+            }),
+        });
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            templates.render(
+                Rejection::RateLimited,
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit exceeded"
+            ),
+            Some((
+                "application/json".to_string(),
+                r#"{"error":"Rate limit exceeded"}"#.to_string()
+            ))
+        );
+        assert_eq!(
+            templates.render(
+                Rejection::Unavailable,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No proxies available"
+            ),
+            Some((
+                "text/html".to_string(),
+                "<h1>503: back soon</h1>".to_string()
+            ))
+        );
+        // Unreadable file: the built-in message stays
+        assert!(templates
+            .render(
+                Rejection::AllFailed,
+                StatusCode::BAD_GATEWAY,
+                "All proxies failed"
+            )
+            .is_none());
+    }
+}
@@ -0,0 +1,221 @@
+//! Circuit breaker for proxies failing requests back to back
+//!
+//! After `failure_threshold` consecutive failures a proxy is benched for the cooldown,
+//! without waiting for a health check to mark it failed. Once the cooldown is over it is
+//! half-opened: one trial request may go through, and its outcome either closes the
+//! breaker or benches the proxy again.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+
+#[derive(Debug, Clone, Copy)]
+struct BreakerSettings {
+    /// Consecutive failures that open the breaker (0 = breaker off)
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+/// Breaker state of one proxy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// In rotation, counting consecutive failures
+    Closed { failures: u32 },
+    /// Benched until the cooldown has passed
+    Open { since: Instant },
+    /// Cooldown over; `trial_at` is set once the trial request has been handed out
+    HalfOpen { trial_at: Option<Instant> },
+}
+
+/// Tracks consecutive failures per proxy and benches proxies whose breaker is open
+pub struct CircuitBreaker {
+    settings: RwLock<BreakerSettings>,
+    states: DashMap<i64, BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(BreakerSettings {
+                failure_threshold: 0,
+                cooldown: Duration::from_secs(30),
+            }),
+            states: DashMap::new(),
+        }
+    }
+
+    /// Open after `failure_threshold` consecutive failures (0 = off) for `cooldown`
+    pub fn configure(&self, failure_threshold: u32, cooldown: Duration) {
+        *self.settings.write() = BreakerSettings {
+            failure_threshold,
+            cooldown,
+        };
+        if failure_threshold == 0 {
+            self.states.clear();
+        }
+    }
+
+    /// Record a failed request; opens the breaker at the threshold or after a failed trial
+    pub fn record_failure(&self, proxy_id: i64) {
+        let threshold = self.settings.read().failure_threshold;
+        if threshold == 0 {
+            return;
+        }
+
+        let mut state = self
+            .states
+            .entry(proxy_id)
+            .or_insert(BreakerState::Closed { failures: 0 });
+        *state = match *state {
+            BreakerState::Closed { failures } if failures + 1 < threshold => BreakerState::Closed {
+                failures: failures + 1,
+            },
+            BreakerState::Open { since } => BreakerState::Open { since },
+            _ => BreakerState::Open {
+                since: Instant::now(),
+            },
+        };
+    }
+
+    /// Record a successful request, closing the breaker
+    pub fn record_success(&self, proxy_id: i64) {
+        self.states.remove(&proxy_id);
+    }
+
+    /// Whether the proxy may be selected now
+    ///
+    /// Half-opens a breaker whose cooldown has passed and hands out its single trial. A
+    /// trial whose result never arrives is handed out again after another cooldown.
+    pub fn allows(&self, proxy_id: i64) -> bool {
+        let Some(mut state) = self.states.get_mut(&proxy_id) else {
+            return true;
+        };
+        let cooldown = self.settings.read().cooldown;
+        let now = Instant::now();
+
+        match *state {
+            BreakerState::Closed { .. } => true,
+            BreakerState::Open { since } if now.duration_since(since) < cooldown => false,
+            BreakerState::HalfOpen {
+                trial_at: Some(trial_at),
+            } if now.duration_since(trial_at) < cooldown => false,
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {
+                *state = BreakerState::HalfOpen {
+                    trial_at: Some(now),
+                };
+                true
+            }
+        }
+    }
+
+    /// Whether the proxy is currently kept out of rotation, without handing out a trial
+    pub fn is_benched(&self, proxy_id: i64) -> bool {
+        let cooldown = self.settings.read().cooldown;
+        match self.state(proxy_id) {
+            BreakerState::Closed { .. } | BreakerState::HalfOpen { trial_at: None } => false,
+            BreakerState::Open { since: at } | BreakerState::HalfOpen { trial_at: Some(at) } => {
+                at.elapsed() < cooldown
+            }
+        }
+    }
+
+    pub fn state(&self, proxy_id: i64) -> BreakerState {
+        self.states
+            .get(&proxy_id)
+            .map(|state| *state)
+            .unwrap_or(BreakerState::Closed { failures: 0 })
+    }
+
+    /// Forget every breaker
+    pub fn clear(&self) {
+        self.states.clear();
+    }
+
+    /// Forget proxies that left the pool
+    pub fn retain(&self, proxy_ids: &[i64]) {
+        self.states.retain(|id, _| proxy_ids.contains(id));
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        let breaker = CircuitBreaker::new();
+        breaker.configure(threshold, cooldown);
+        breaker
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = breaker(3, Duration::from_secs(60));
+
+        breaker.record_failure(1);
+        breaker.record_failure(1);
+        assert!(breaker.allows(1));
+
+        // A success resets the count
+        breaker.record_success(1);
+        breaker.record_failure(1);
+        breaker.record_failure(1);
+        assert_eq!(breaker.state(1), BreakerState::Closed { failures: 2 });
+
+        breaker.record_failure(1);
+        assert!(matches!(breaker.state(1), BreakerState::Open { .. }));
+        assert!(breaker.is_benched(1));
+        assert!(!breaker.allows(1));
+        assert!(breaker.allows(2));
+        assert!(!breaker.is_benched(2));
+    }
+
+    #[test]
+    fn test_half_open_allows_a_single_trial() {
+        let breaker = breaker(1, Duration::from_millis(20));
+
+        breaker.record_failure(1);
+        assert!(!breaker.allows(1));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allows(1));
+        assert!(matches!(breaker.state(1), BreakerState::HalfOpen { .. }));
+        assert!(!breaker.allows(1));
+    }
+
+    #[test]
+    fn test_trial_result_closes_or_reopens() {
+        let breaker = breaker(1, Duration::from_millis(20));
+
+        // A failed trial benches the proxy for another cooldown
+        breaker.record_failure(1);
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allows(1));
+        breaker.record_failure(1);
+        assert!(matches!(breaker.state(1), BreakerState::Open { .. }));
+        assert!(!breaker.allows(1));
+
+        // A successful trial closes the breaker
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allows(1));
+        breaker.record_success(1);
+        assert_eq!(breaker.state(1), BreakerState::Closed { failures: 0 });
+        assert!(breaker.allows(1));
+        assert!(breaker.allows(1));
+    }
+
+    #[test]
+    fn test_disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new();
+        for _ in 0..100 {
+            breaker.record_failure(1);
+        }
+        assert!(breaker.allows(1));
+    }
+}
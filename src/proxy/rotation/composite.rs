@@ -0,0 +1,407 @@
+//! Composite scoring proxy selection strategy
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+
+use super::{ConnectionTracker, ProxySelector};
+use crate::error::{Result, RotaError};
+use crate::models::Proxy;
+
+/// Smoothing factor for the latency EWMA (weight of the newest sample)
+const EWMA_ALPHA: f64 = 0.3;
+
+/// How much each signal counts towards a proxy's score
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompositeWeights {
+    pub success_rate: f64,
+    pub latency: f64,
+    pub connections: f64,
+    /// Pick proxies with probability proportional to their score instead of the best one
+    pub proportional: bool,
+}
+
+impl Default for CompositeWeights {
+    fn default() -> Self {
+        Self {
+            success_rate: 0.5,
+            latency: 0.3,
+            connections: 0.2,
+            proportional: false,
+        }
+    }
+}
+
+impl CompositeWeights {
+    /// Weights clamped to be non-negative; all zero counts every signal equally
+    fn normalized(&self) -> (f64, f64, f64) {
+        let weights = (
+            self.success_rate.max(0.0),
+            self.latency.max(0.0),
+            self.connections.max(0.0),
+        );
+        if weights.0 + weights.1 + weights.2 > 0.0 {
+            weights
+        } else {
+            (1.0, 1.0, 1.0)
+        }
+    }
+}
+
+/// Selects proxies by a weighted score of success rate, latency and active connections
+///
+/// Each signal is normalized to 0..=1 across the pool (1 = best) before weighting.
+/// Success rate and latency start from the proxy's stored statistics and follow
+/// `report_result`; proxies without data get the pool median so they still get traffic.
+pub struct CompositeSelector {
+    proxies: RwLock<Vec<Arc<Proxy>>>,
+    weights: CompositeWeights,
+    /// (successful, total) requests per proxy
+    outcomes: DashMap<i64, (u64, u64)>,
+    latencies: DashMap<i64, f64>,
+    tracker: ConnectionTracker,
+}
+
+impl CompositeSelector {
+    pub fn new() -> Self {
+        Self::with_weights(CompositeWeights::default())
+    }
+
+    pub fn with_weights(weights: CompositeWeights) -> Self {
+        Self {
+            proxies: RwLock::new(Vec::new()),
+            weights,
+            outcomes: DashMap::new(),
+            latencies: DashMap::new(),
+            tracker: ConnectionTracker::new(),
+        }
+    }
+
+    fn success_rate(&self, proxy_id: i64) -> Option<f64> {
+        self.outcomes
+            .get(&proxy_id)
+            .filter(|o| o.1 > 0)
+            .map(|o| o.0 as f64 / o.1 as f64)
+    }
+
+    /// Score of each proxy in `proxies`, higher is better
+    pub fn scores(&self, proxies: &[Arc<Proxy>]) -> Vec<f64> {
+        let ids: Vec<i64> = proxies.iter().map(|p| p.id as i64).collect();
+        let success: Vec<Option<f64>> = ids.iter().map(|id| self.success_rate(*id)).collect();
+        let latency: Vec<Option<f64>> = ids
+            .iter()
+            .map(|id| self.latencies.get(id).map(|v| *v))
+            .collect();
+        let connections: Vec<f64> = ids.iter().map(|id| self.tracker.get(*id) as f64).collect();
+
+        let success = fill_median(&success);
+        let latency = fill_median(&latency);
+
+        let (min_latency, max_latency) = min_max(&latency);
+        let max_connections = min_max(&connections).1;
+        let (w_success, w_latency, w_connections) = self.weights.normalized();
+        let total = w_success + w_latency + w_connections;
+
+        (0..proxies.len())
+            .map(|i| {
+                let latency_score = if max_latency > min_latency {
+                    (max_latency - latency[i]) / (max_latency - min_latency)
+                } else {
+                    1.0
+                };
+                let connection_score = if max_connections > 0.0 {
+                    1.0 - connections[i] / max_connections
+                } else {
+                    1.0
+                };
+                (w_success * success[i]
+                    + w_latency * latency_score
+                    + w_connections * connection_score)
+                    / total
+            })
+            .collect()
+    }
+}
+
+impl Default for CompositeSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Values with the gaps filled in by the median of the known ones (1.0 when none are known)
+fn fill_median(values: &[Option<f64>]) -> Vec<f64> {
+    let mut known: Vec<f64> = values.iter().flatten().copied().collect();
+    known.sort_by(|a, b| a.total_cmp(b));
+    let median = known.get(known.len() / 2).copied().unwrap_or(1.0);
+    values.iter().map(|v| v.unwrap_or(median)).collect()
+}
+
+fn min_max(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        })
+}
+
+#[async_trait]
+impl ProxySelector for CompositeSelector {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
+        let proxies = self.proxies.read();
+
+        if proxies.is_empty() {
+            return Err(RotaError::NoProxiesAvailable);
+        }
+
+        // Scores are normalized across the whole pool, then ineligible proxies are dropped.
+        let scored: Vec<(&Arc<Proxy>, f64)> = proxies
+            .iter()
+            .zip(self.scores(&proxies))
+            .filter(|(proxy, _)| filter(proxy))
+            .collect();
+        let mut rng = rand::thread_rng();
+
+        if self.weights.proportional {
+            if let Ok(dist) = WeightedIndex::new(scored.iter().map(|(_, score)| *score)) {
+                return Ok(scored[dist.sample(&mut rng)].0.clone());
+            }
+        }
+
+        // Best score, ties broken at random so equal proxies share the load
+        let best = scored
+            .iter()
+            .map(|(_, score)| *score)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let candidates: Vec<&Arc<Proxy>> = scored
+            .iter()
+            .filter(|(_, score)| *score >= best)
+            .map(|(proxy, _)| *proxy)
+            .collect();
+        candidates
+            .choose(&mut rng)
+            .map(|proxy| (*proxy).clone())
+            .ok_or(RotaError::NoProxiesAvailable)
+    }
+
+    async fn refresh(&self, proxies: Vec<Proxy>) -> Result<()> {
+        // Seed proxies we haven't observed yet from their stored statistics.
+        for proxy in &proxies {
+            if proxy.requests > 0 {
+                self.outcomes.entry(proxy.id as i64).or_insert((
+                    proxy.successful_requests.max(0) as u64,
+                    proxy.requests as u64,
+                ));
+            }
+            if proxy.avg_response_time > 0 {
+                self.latencies
+                    .entry(proxy.id as i64)
+                    .or_insert(proxy.avg_response_time as f64);
+            }
+        }
+        self.outcomes
+            .retain(|id, _| proxies.iter().any(|p| p.id as i64 == *id));
+        self.latencies
+            .retain(|id, _| proxies.iter().any(|p| p.id as i64 == *id));
+
+        let mut guard = self.proxies.write();
+        *guard = proxies.into_iter().map(Arc::new).collect();
+        Ok(())
+    }
+
+    fn available_count(&self) -> usize {
+        self.proxies.read().len()
+    }
+
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.proxies.read().iter().any(|p| p.id as i64 == proxy_id)
+    }
+
+    fn strategy_name(&self) -> &'static str {
+        "composite"
+    }
+
+    fn acquire(&self, proxy_id: i64) {
+        self.tracker.acquire(proxy_id);
+    }
+
+    fn release(&self, proxy_id: i64) {
+        self.tracker.release(proxy_id);
+    }
+
+    fn active_connections(&self, proxy_id: i64) -> usize {
+        self.tracker.get(proxy_id)
+    }
+
+    fn report_result(&self, proxy_id: i64, success: bool, latency: Duration) {
+        let mut outcome = self.outcomes.entry(proxy_id).or_insert((0, 0));
+        outcome.0 += success as u64;
+        outcome.1 += 1;
+        drop(outcome);
+
+        if success {
+            let sample = latency.as_secs_f64() * 1000.0;
+            self.latencies
+                .entry(proxy_id)
+                .and_modify(|ewma| *ewma = EWMA_ALPHA * sample + (1.0 - EWMA_ALPHA) * *ewma)
+                .or_insert(sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_proxy(id: i32, successful: i64, requests: i64, latency_ms: i32) -> Proxy {
+        Proxy {
+            id,
+            address: format!("127.0.0.1:{}", 8080 + id),
+            protocol: "http".to_string(),
+            username: None,
+            password: None,
+            status: "active".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
+            requests,
+            successful_requests: successful,
+            failed_requests: requests - successful,
+            avg_response_time: latency_ms,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
+            last_error: None,
+            auto_delete_after_failed_seconds: None,
+            invalid_since: None,
+            failure_reasons: serde_json::Value::Array(Vec::new()),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Reliable but slow (1) versus flaky but fast (2)
+    async fn selector_with(weights: CompositeWeights) -> CompositeSelector {
+        let selector = CompositeSelector::with_weights(weights);
+        selector
+            .refresh(vec![
+                create_test_proxy(1, 99, 100, 900),
+                create_test_proxy(2, 60, 100, 100),
+            ])
+            .await
+            .unwrap();
+        selector
+    }
+
+    async fn best(selector: &CompositeSelector) -> i32 {
+        selector.select().await.unwrap().id
+    }
+
+    #[tokio::test]
+    async fn test_composite_empty() {
+        let selector = CompositeSelector::new();
+        assert!(matches!(
+            selector.select().await,
+            Err(RotaError::NoProxiesAvailable)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_weights_decide_ranking() {
+        let reliability = selector_with(CompositeWeights {
+            success_rate: 1.0,
+            latency: 0.2,
+            connections: 0.0,
+            proportional: false,
+        })
+        .await;
+        assert_eq!(best(&reliability).await, 1);
+
+        let speed = selector_with(CompositeWeights {
+            success_rate: 0.2,
+            latency: 1.0,
+            connections: 0.0,
+            proportional: false,
+        })
+        .await;
+        assert_eq!(best(&speed).await, 2);
+
+        let proxies = speed.proxies.read().clone();
+        let scores = speed.scores(&proxies);
+        assert!((scores[0] - (0.2 * 0.99) / 1.2).abs() < 1e-9);
+        assert!((scores[1] - (0.2 * 0.6 + 1.0) / 1.2).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_busy_proxy_loses_on_connections() {
+        let selector = CompositeSelector::with_weights(CompositeWeights {
+            success_rate: 0.0,
+            latency: 0.0,
+            connections: 1.0,
+            proportional: false,
+        });
+        selector
+            .refresh(vec![
+                create_test_proxy(1, 10, 10, 100),
+                create_test_proxy(2, 10, 10, 100),
+            ])
+            .await
+            .unwrap();
+
+        selector.acquire(1);
+        for _ in 0..20 {
+            assert_eq!(best(&selector).await, 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reported_results_move_the_score() {
+        let selector = CompositeSelector::with_weights(CompositeWeights {
+            success_rate: 1.0,
+            latency: 0.0,
+            connections: 0.0,
+            proportional: false,
+        });
+        selector
+            .refresh(vec![
+                create_test_proxy(1, 10, 10, 100),
+                create_test_proxy(2, 9, 10, 100),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(best(&selector).await, 1);
+
+        for _ in 0..10 {
+            selector.report_result(1, false, Duration::from_millis(100));
+        }
+        assert_eq!(best(&selector).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_proportional_sampling_favors_higher_score() {
+        let selector = selector_with(CompositeWeights {
+            success_rate: 0.0,
+            latency: 1.0,
+            connections: 0.0,
+            proportional: true,
+        })
+        .await;
+
+        // Proxy 1 scores zero on latency and is never picked
+        for _ in 0..50 {
+            assert_eq!(best(&selector).await, 2);
+        }
+    }
+}
@@ -3,15 +3,84 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
 
-use super::{create_selector, ProxySelector, RotationStrategy, TimeBasedSelector};
-use crate::error::Result;
-use crate::models::Proxy;
+use super::breaker::CircuitBreaker;
+use super::recovery::RecoveryGate;
+use super::{
+    create_selector, CompositeSelector, CompositeWeights, LeastConnectionsSelector, ProxySelector,
+    RotationStrategy, TieBreak, TimeBasedSelector,
+};
+use crate::error::{Result, RotaError};
+use crate::models::{HostStrategyRule, Proxy, RotationSettings, Settings};
+
+/// Host-pattern overrides, each pointing at one selector per distinct strategy
+#[derive(Default)]
+struct HostRules {
+    rules: Vec<(HostStrategyRule, usize)>,
+    selectors: Vec<Arc<dyn ProxySelector>>,
+}
+
+/// Inputs the underlying selector is built from
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct StrategyParams {
+    strategy: RotationStrategy,
+    time_based_interval: Duration,
+    tie_break: TieBreak,
+    max_connections_per_proxy: usize,
+    composite: CompositeWeights,
+}
+
+impl StrategyParams {
+    fn from_rotation(rotation: &RotationSettings) -> Self {
+        Self {
+            strategy: RotationStrategy::from_str(&rotation.method),
+            time_based_interval: Duration::from_secs(rotation.time_based.interval.max(1) as u64),
+            tie_break: TieBreak::parse(&rotation.least_connections_tie_break),
+            max_connections_per_proxy: rotation.max_connections_per_proxy.max(0) as usize,
+            composite: CompositeWeights {
+                success_rate: rotation.composite.success_rate_weight,
+                latency: rotation.composite.latency_weight,
+                connections: rotation.composite.connections_weight,
+                proportional: rotation.composite.proportional,
+            },
+        }
+    }
+}
 
 /// A proxy selector that can swap the underlying strategy at runtime.
 pub struct DynamicProxySelector {
     inner: RwLock<Arc<dyn ProxySelector>>,
     proxies: RwLock<Vec<Proxy>>,
+    host_rules: RwLock<HostRules>,
+    recovery: Arc<RecoveryGate>,
+    breaker: Arc<CircuitBreaker>,
+    tie_break: RwLock<TieBreak>,
+    max_connections_per_proxy: RwLock<usize>,
+    composite: RwLock<CompositeWeights>,
+    /// Parameters last applied through `apply_rotation_settings`
+    applied: RwLock<Option<StrategyParams>>,
+}
+
+fn build_selector(
+    strategy: RotationStrategy,
+    time_based_interval: Duration,
+    tie_break: TieBreak,
+    max_connections_per_proxy: usize,
+    composite: CompositeWeights,
+) -> Arc<dyn ProxySelector> {
+    match strategy {
+        RotationStrategy::TimeBased => {
+            Arc::new(TimeBasedSelector::with_interval(time_based_interval))
+        }
+        RotationStrategy::LeastConnections => Arc::new(LeastConnectionsSelector::with_options(
+            tie_break,
+            max_connections_per_proxy,
+        )),
+        RotationStrategy::Composite => Arc::new(CompositeSelector::with_weights(composite)),
+        _ => Arc::from(create_selector(strategy)),
+    }
 }
 
 impl DynamicProxySelector {
@@ -19,20 +88,54 @@ impl DynamicProxySelector {
         Self {
             inner: RwLock::new(initial),
             proxies: RwLock::new(Vec::new()),
+            host_rules: RwLock::new(HostRules::default()),
+            recovery: Arc::new(RecoveryGate::new()),
+            breaker: Arc::new(CircuitBreaker::new()),
+            tie_break: RwLock::new(TieBreak::default()),
+            max_connections_per_proxy: RwLock::new(0),
+            composite: RwLock::new(CompositeWeights::default()),
+            applied: RwLock::new(None),
         }
     }
 
+    /// Tie-break policy for least-connections selectors built from now on
+    pub fn set_tie_break(&self, tie_break: TieBreak) {
+        *self.tie_break.write() = tie_break;
+    }
+
+    /// Per-proxy connection cap for least-connections selectors built from now on (0 = unlimited)
+    pub fn set_max_connections_per_proxy(&self, max_connections_per_proxy: usize) {
+        *self.max_connections_per_proxy.write() = max_connections_per_proxy;
+    }
+
+    /// Score weights for composite selectors built from now on
+    pub fn set_composite_weights(&self, weights: CompositeWeights) {
+        *self.composite.write() = weights;
+    }
+
+    /// Require recovered proxies to reach `min_success_rate` percent over their last
+    /// `window` results before getting more than probe traffic (0 = off)
+    pub fn set_recovery_gate(&self, min_success_rate: f64, window: usize) {
+        self.recovery.configure(min_success_rate, window);
+    }
+
+    /// Bench proxies for `cooldown` after `failure_threshold` consecutive failures (0 = off)
+    pub fn set_circuit_breaker(&self, failure_threshold: u32, cooldown: Duration) {
+        self.breaker.configure(failure_threshold, cooldown);
+    }
+
     pub async fn set_strategy(
         &self,
         strategy: RotationStrategy,
         time_based_interval: Duration,
     ) -> Result<()> {
-        let selector: Arc<dyn ProxySelector> = match strategy {
-            RotationStrategy::TimeBased => {
-                Arc::new(TimeBasedSelector::with_interval(time_based_interval))
-            }
-            _ => Arc::from(create_selector(strategy)),
-        };
+        let selector = build_selector(
+            strategy,
+            time_based_interval,
+            *self.tie_break.read(),
+            *self.max_connections_per_proxy.read(),
+            *self.composite.read(),
+        );
 
         // Carry over the latest proxy list to the new selector.
         let proxies = self.proxies.read().clone();
@@ -41,17 +144,289 @@ impl DynamicProxySelector {
         *self.inner.write() = selector;
         Ok(())
     }
+
+    /// Switch to the strategy configured in `rotation`
+    ///
+    /// The selector is only rebuilt (resetting its rotation state) when the strategy or one
+    /// of its parameters changed. Returns whether it was.
+    pub async fn apply_rotation_settings(&self, rotation: &RotationSettings) -> Result<bool> {
+        let params = StrategyParams::from_rotation(rotation);
+        if *self.applied.read() == Some(params) {
+            return Ok(false);
+        }
+
+        self.set_tie_break(params.tie_break);
+        self.set_max_connections_per_proxy(params.max_connections_per_proxy);
+        self.set_composite_weights(params.composite);
+        self.set_strategy(params.strategy, params.time_based_interval)
+            .await?;
+        *self.applied.write() = Some(params);
+        Ok(true)
+    }
+
+    /// Throw away all rotation state and start over from `proxies`
+    ///
+    /// Fresh selectors are built for the strategy and host rules in `rotation`, and
+    /// circuit breakers and probation are forgotten, so connection counts, cursors and
+    /// learned statistics all start from zero. The proxy list is swapped in with them.
+    pub async fn rebuild(
+        &self,
+        rotation: &RotationSettings,
+        mut proxies: Vec<Proxy>,
+    ) -> Result<()> {
+        retain_rotatable(&mut proxies);
+        let params = StrategyParams::from_rotation(rotation);
+
+        self.set_tie_break(params.tie_break);
+        self.set_max_connections_per_proxy(params.max_connections_per_proxy);
+        self.set_composite_weights(params.composite);
+
+        let selector = build_selector(
+            params.strategy,
+            params.time_based_interval,
+            params.tie_break,
+            params.max_connections_per_proxy,
+            params.composite,
+        );
+        selector.refresh(proxies.clone()).await?;
+        let host_rules = self
+            .build_host_rules(&rotation.host_rules, params.time_based_interval, &proxies)
+            .await?;
+
+        self.breaker.clear();
+        self.recovery.clear();
+        *self.proxies.write() = proxies;
+        *self.host_rules.write() = host_rules;
+        *self.inner.write() = selector;
+        *self.applied.write() = Some(params);
+        Ok(())
+    }
+
+    /// Follow settings updates until `shutdown`, switching strategies as they change
+    pub async fn watch_settings(
+        &self,
+        mut settings_rx: watch::Receiver<Settings>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        loop {
+            tokio::select! {
+                changed = settings_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let rotation = settings_rx.borrow_and_update().rotation.clone();
+                    match self.apply_rotation_settings(&rotation).await {
+                        Ok(true) => info!("Rotation strategy switched to {}", self.strategy_name()),
+                        Ok(false) => {}
+                        Err(e) => error!("Failed to switch rotation strategy: {}", e),
+                    }
+                }
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replace the per-host strategy rules
+    ///
+    /// Rules sharing a strategy share one selector so connection tracking stays accurate.
+    pub async fn set_host_rules(
+        &self,
+        rules: &[HostStrategyRule],
+        time_based_interval: Duration,
+    ) -> Result<()> {
+        let proxies = self.proxies.read().clone();
+        let host_rules = self
+            .build_host_rules(rules, time_based_interval, &proxies)
+            .await?;
+        *self.host_rules.write() = host_rules;
+        Ok(())
+    }
+
+    async fn build_host_rules(
+        &self,
+        rules: &[HostStrategyRule],
+        time_based_interval: Duration,
+        proxies: &[Proxy],
+    ) -> Result<HostRules> {
+        let tie_break = *self.tie_break.read();
+        let max_connections_per_proxy = *self.max_connections_per_proxy.read();
+        let composite = *self.composite.read();
+        let mut host_rules = HostRules::default();
+        let mut strategies: Vec<RotationStrategy> = Vec::new();
+
+        for rule in rules {
+            let strategy = RotationStrategy::from_str(&rule.method);
+            let index = match strategies.iter().position(|s| *s == strategy) {
+                Some(index) => index,
+                None => {
+                    strategies.push(strategy);
+                    host_rules.selectors.push(Arc::new(GatedSelector {
+                        inner: build_selector(
+                            strategy,
+                            time_based_interval,
+                            tie_break,
+                            max_connections_per_proxy,
+                            composite,
+                        ),
+                        recovery: self.recovery.clone(),
+                        breaker: self.breaker.clone(),
+                    }));
+                    strategies.len() - 1
+                }
+            };
+            host_rules.rules.push((rule.clone(), index));
+        }
+
+        for selector in &host_rules.selectors {
+            selector.refresh(proxies.to_vec()).await?;
+        }
+
+        Ok(host_rules)
+    }
+
+    /// Whether the proxy's circuit breaker currently keeps it out of rotation
+    pub fn is_benched(&self, proxy_id: i64) -> bool {
+        self.breaker.is_benched(proxy_id)
+    }
+
+    /// Whether the proxy only gets probe traffic until it proves out after recovering
+    pub fn is_on_probation(&self, proxy_id: i64) -> bool {
+        self.recovery.is_on_probation(proxy_id)
+    }
+
+    fn rule_selectors(&self) -> Vec<Arc<dyn ProxySelector>> {
+        self.host_rules.read().selectors.clone()
+    }
+}
+
+/// Keep only proxies that can enter rotation
+///
+/// Proxies disabled by an operator never do, whatever their health. Neither do proxies
+/// whose protocol we can't speak: every connection through them would fail, using up a
+/// retry each time.
+fn retain_rotatable(proxies: &mut Vec<Proxy>) {
+    proxies.retain(|p| {
+        if p.protocol_enum().is_none() {
+            warn!(
+                proxy_id = p.id,
+                protocol = %p.protocol,
+                "Leaving proxy with unsupported protocol out of rotation"
+            );
+            return false;
+        }
+        p.enabled
+    });
+}
+
+/// Pick from `selector` while honoring the circuit breaker and the recovery gate
+async fn select_gated(
+    selector: &dyn ProxySelector,
+    recovery: &RecoveryGate,
+    breaker: &CircuitBreaker,
+    filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+) -> Result<Arc<Proxy>> {
+    // Proxies with an open circuit breaker are benched until their trial request.
+    let filter = &|p: &Proxy| filter(p) && !breaker.is_benched(p.id as i64);
+    // Probe turns go to proxies on probation; otherwise they're kept out unless
+    // nothing else is eligible.
+    let on_probation = recovery.probe_turn();
+
+    // The trial of a half-open breaker is claimed only once its proxy is picked; a
+    // concurrent request may have claimed it first, which benches the proxy again.
+    for _ in 0..=selector.available_count() {
+        let proxy = match selector
+            .select_matching(&|p| {
+                recovery.is_on_probation(p.id as i64) == on_probation && filter(p)
+            })
+            .await
+        {
+            Ok(proxy) => proxy,
+            Err(_) => selector.select_matching(filter).await?,
+        };
+        if breaker.allows(proxy.id as i64) {
+            return Ok(proxy);
+        }
+    }
+    Err(RotaError::NoProxiesAvailable)
+}
+
+/// Host-rule selector that shares the dynamic selector's circuit breaker and recovery gate
+struct GatedSelector {
+    inner: Arc<dyn ProxySelector>,
+    recovery: Arc<RecoveryGate>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+#[async_trait]
+impl ProxySelector for GatedSelector {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
+        select_gated(&*self.inner, &self.recovery, &self.breaker, filter).await
+    }
+
+    async fn refresh(&self, proxies: Vec<Proxy>) -> Result<()> {
+        self.inner.refresh(proxies).await
+    }
+
+    fn available_count(&self) -> usize {
+        self.inner.available_count()
+    }
+
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.inner.contains(proxy_id)
+    }
+
+    fn strategy_name(&self) -> &'static str {
+        self.inner.strategy_name()
+    }
+
+    fn acquire(&self, proxy_id: i64) {
+        self.inner.acquire(proxy_id);
+    }
+
+    fn release(&self, proxy_id: i64) {
+        self.inner.release(proxy_id);
+    }
+
+    fn active_connections(&self, proxy_id: i64) -> usize {
+        self.inner.active_connections(proxy_id)
+    }
+
+    fn report_result(&self, proxy_id: i64, success: bool, latency: Duration) {
+        self.inner.report_result(proxy_id, success, latency);
+    }
+
+    fn is_saturated(&self, proxy_id: i64) -> bool {
+        self.inner.is_saturated(proxy_id)
+    }
 }
 
 #[async_trait]
 impl ProxySelector for DynamicProxySelector {
-    async fn select(&self) -> Result<Arc<Proxy>> {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
         let selector = self.inner.read().clone();
-        selector.select().await
+        select_gated(&*selector, &self.recovery, &self.breaker, filter).await
     }
 
-    async fn refresh(&self, proxies: Vec<Proxy>) -> Result<()> {
+    async fn refresh(&self, mut proxies: Vec<Proxy>) -> Result<()> {
+        retain_rotatable(&mut proxies);
+        let ids: Vec<i64> = proxies.iter().map(|p| p.id as i64).collect();
+        self.recovery.retain(&ids);
+        self.breaker.retain(&ids);
+
         *self.proxies.write() = proxies.clone();
+        for selector in self.rule_selectors() {
+            selector.refresh(proxies.clone()).await?;
+        }
         let selector = self.inner.read().clone();
         selector.refresh(proxies).await
     }
@@ -60,16 +435,68 @@ impl ProxySelector for DynamicProxySelector {
         self.inner.read().available_count()
     }
 
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.proxies.read().iter().any(|p| p.id as i64 == proxy_id)
+    }
+
     fn strategy_name(&self) -> &'static str {
         self.inner.read().strategy_name()
     }
 
     fn acquire(&self, proxy_id: i64) {
         self.inner.read().acquire(proxy_id);
+        for selector in self.host_rules.read().selectors.iter() {
+            selector.acquire(proxy_id);
+        }
     }
 
     fn release(&self, proxy_id: i64) {
         self.inner.read().release(proxy_id);
+        for selector in self.host_rules.read().selectors.iter() {
+            selector.release(proxy_id);
+        }
+    }
+
+    fn active_connections(&self, proxy_id: i64) -> usize {
+        // Every selector sees the same acquire/release calls; any that tracks them knows the count
+        self.host_rules
+            .read()
+            .selectors
+            .iter()
+            .map(|selector| selector.active_connections(proxy_id))
+            .fold(self.inner.read().active_connections(proxy_id), usize::max)
+    }
+
+    fn report_result(&self, proxy_id: i64, success: bool, latency: Duration) {
+        self.recovery.record(proxy_id, success);
+        if success {
+            self.breaker.record_success(proxy_id);
+        }
+        self.inner.read().report_result(proxy_id, success, latency);
+        for selector in self.host_rules.read().selectors.iter() {
+            selector.report_result(proxy_id, success, latency);
+        }
+    }
+
+    fn is_saturated(&self, proxy_id: i64) -> bool {
+        self.inner.read().is_saturated(proxy_id)
+    }
+
+    fn report_failure(&self, proxy_id: i64) {
+        self.breaker.record_failure(proxy_id);
+    }
+
+    fn mark_recovered(&self, proxy_id: i64) {
+        self.recovery.start(proxy_id);
+    }
+
+    fn selector_for_host(&self, host: &str) -> Option<Arc<dyn ProxySelector>> {
+        let host_rules = self.host_rules.read();
+        host_rules
+            .rules
+            .iter()
+            .find(|(rule, _)| rule.matches(host))
+            .map(|(_, index)| host_rules.selectors[*index].clone())
     }
 }
 
@@ -77,7 +504,7 @@ impl ProxySelector for DynamicProxySelector {
 mod tests {
     use super::*;
 
-    use crate::proxy::rotation::RoundRobinSelector;
+    use crate::proxy::rotation::{LeastConnectionsSelector, RoundRobinSelector};
 
     fn create_test_proxy(id: i32, address: &str) -> Proxy {
         Proxy {
@@ -87,11 +514,20 @@ mod tests {
             username: None,
             password: None,
             status: "idle".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
             requests: 0,
             successful_requests: 0,
             failed_requests: 0,
             avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
             last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
             last_error: None,
             auto_delete_after_failed_seconds: None,
             invalid_since: None,
@@ -127,6 +563,52 @@ mod tests {
         assert_eq!(selector.select().await.unwrap().id, 99);
     }
 
+    #[tokio::test]
+    async fn test_dynamic_selector_excludes_disabled_proxies() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+
+        let mut disabled = create_test_proxy(2, "127.0.0.1:8082");
+        disabled.status = "active".to_string();
+        disabled.enabled = false;
+
+        selector
+            .refresh(vec![create_test_proxy(1, "127.0.0.1:8081"), disabled])
+            .await
+            .unwrap();
+
+        assert_eq!(selector.available_count(), 1);
+        for _ in 0..4 {
+            assert_eq!(selector.select().await.unwrap().id, 1);
+        }
+
+        // Switching strategies must not bring the disabled proxy back.
+        selector
+            .set_strategy(RotationStrategy::RoundRobin, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(selector.available_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_selector_excludes_unsupported_protocols() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+
+        let mut unknown = create_test_proxy(2, "127.0.0.1:8082");
+        unknown.protocol = "ftp".to_string();
+        selector
+            .refresh(vec![create_test_proxy(1, "127.0.0.1:8081"), unknown])
+            .await
+            .unwrap();
+
+        assert_eq!(selector.available_count(), 1);
+        assert!(!selector.contains(2));
+        for _ in 0..4 {
+            assert_eq!(selector.select().await.unwrap().id, 1);
+        }
+    }
+
     #[tokio::test]
     async fn test_dynamic_selector_switch_strategy_preserves_proxies_and_tracking() {
         let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
@@ -168,4 +650,316 @@ mod tests {
 
         assert_eq!(selector.select().await.unwrap().id, 1);
     }
+
+    #[tokio::test]
+    async fn test_active_connections_follow_acquire_and_release() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+        selector
+            .refresh(vec![create_test_proxy(1, "127.0.0.1:8081")])
+            .await
+            .unwrap();
+
+        // Round-robin doesn't track connections
+        selector.acquire(1);
+        assert_eq!(selector.active_connections(1), 0);
+        selector.release(1);
+
+        selector
+            .set_strategy(RotationStrategy::LeastConnections, Duration::from_secs(60))
+            .await
+            .unwrap();
+        selector.acquire(1);
+        selector.acquire(1);
+        assert_eq!(selector.active_connections(1), 2);
+        assert_eq!(selector.active_connections(2), 0);
+
+        selector.release(1);
+        assert_eq!(selector.active_connections(1), 1);
+        selector.release(1);
+        assert_eq!(selector.active_connections(1), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_resets_tracking_and_keeps_proxies() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+        selector.set_circuit_breaker(1, Duration::from_secs(60));
+        let proxies = vec![
+            create_test_proxy(1, "127.0.0.1:8081"),
+            create_test_proxy(2, "127.0.0.1:8082"),
+            create_test_proxy(3, "127.0.0.1:8083"),
+        ];
+
+        let rotation = RotationSettings {
+            method: "least_connections".to_string(),
+            least_connections_tie_break: "first".to_string(),
+            ..Default::default()
+        };
+        selector.apply_rotation_settings(&rotation).await.unwrap();
+        selector.refresh(proxies.clone()).await.unwrap();
+
+        // Accumulate state: connections on 1 and 2, breaker open on 3
+        selector.acquire(1);
+        selector.acquire(2);
+        selector.report_failure(3);
+        assert!(selector.is_benched(3));
+        assert_eq!(selector.select().await.unwrap().id, 1);
+
+        selector.rebuild(&rotation, proxies).await.unwrap();
+
+        assert_eq!(selector.strategy_name(), "least_connections");
+        assert_eq!(selector.available_count(), 3);
+        assert!(!selector.is_benched(3));
+        // With every count back at zero the lowest id wins again
+        assert_eq!(selector.select().await.unwrap().id, 1);
+
+        // Connections released after the rebuild don't go negative
+        selector.release(1);
+        selector.acquire(1);
+        assert_eq!(selector.select().await.unwrap().id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_restarts_round_robin_cursor() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+        let proxies = vec![
+            create_test_proxy(1, "127.0.0.1:8081"),
+            create_test_proxy(2, "127.0.0.1:8082"),
+        ];
+        selector.refresh(proxies.clone()).await.unwrap();
+        assert_eq!(selector.select().await.unwrap().id, 1);
+
+        let rotation = RotationSettings {
+            method: "round_robin".to_string(),
+            ..Default::default()
+        };
+        selector.rebuild(&rotation, proxies).await.unwrap();
+
+        assert_eq!(selector.available_count(), 2);
+        assert_eq!(selector.select().await.unwrap().id, 1);
+        assert_eq!(selector.select().await.unwrap().id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_host_rules_route_matching_hosts_to_rule_strategy() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(LeastConnectionsSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "127.0.0.1:8081"),
+                create_test_proxy(2, "127.0.0.1:8082"),
+            ])
+            .await
+            .unwrap();
+
+        selector
+            .set_host_rules(
+                &[HostStrategyRule {
+                    pattern: "*.example.com".to_string(),
+                    method: "round_robin".to_string(),
+                }],
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+
+        let rule_selector = selector.selector_for_host("api.example.com").unwrap();
+        assert_eq!(rule_selector.strategy_name(), "round_robin");
+        assert_eq!(rule_selector.available_count(), 2);
+        assert_eq!(rule_selector.select().await.unwrap().id, 1);
+        assert_eq!(rule_selector.select().await.unwrap().id, 2);
+
+        assert!(selector.selector_for_host("other.org").is_none());
+        assert_eq!(selector.strategy_name(), "least_connections");
+
+        // Later refreshes reach the rule selectors too.
+        selector
+            .refresh(vec![create_test_proxy(3, "127.0.0.1:8083")])
+            .await
+            .unwrap();
+        assert_eq!(rule_selector.select().await.unwrap().id, 3);
+    }
+
+    #[tokio::test]
+    async fn test_host_rules_share_selector_per_strategy() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+
+        let rule = |pattern: &str| HostStrategyRule {
+            pattern: pattern.to_string(),
+            method: "least_connections".to_string(),
+        };
+        selector
+            .set_host_rules(&[rule("a.com"), rule("b.com")], Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let a = selector.selector_for_host("a.com").unwrap();
+        let b = selector.selector_for_host("b.com").unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+    }
+
+    #[tokio::test]
+    async fn test_host_rules_skip_benched_proxies() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+        selector.set_circuit_breaker(2, Duration::from_secs(60));
+        selector
+            .set_host_rules(
+                &[HostStrategyRule {
+                    pattern: "*.example.com".to_string(),
+                    method: "round_robin".to_string(),
+                }],
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "127.0.0.1:8081"),
+                create_test_proxy(2, "127.0.0.1:8082"),
+            ])
+            .await
+            .unwrap();
+
+        selector.report_failure(2);
+        selector.report_failure(2);
+
+        let rule_selector = selector.selector_for_host("api.example.com").unwrap();
+        for _ in 0..6 {
+            assert_eq!(rule_selector.select().await.unwrap().id, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recovered_proxy_is_under_selected_until_it_proves_out() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+        selector.set_recovery_gate(80.0, 5);
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "127.0.0.1:8081"),
+                create_test_proxy(2, "127.0.0.1:8082"),
+            ])
+            .await
+            .unwrap();
+        selector.mark_recovered(2);
+
+        let count_recovered = |picks: Vec<i32>| picks.into_iter().filter(|&id| id == 2).count();
+        let mut picks = Vec::new();
+        for _ in 0..100 {
+            picks.push(selector.select().await.unwrap().id);
+        }
+        let probes = count_recovered(picks);
+        assert!(probes > 0 && probes <= 10, "got {} probes", probes);
+
+        // A poor recent record keeps it on probation.
+        for success in [true, false, false, true, false] {
+            selector.report_result(2, success, Duration::from_millis(10));
+        }
+        let mut picks = Vec::new();
+        for _ in 0..100 {
+            picks.push(selector.select().await.unwrap().id);
+        }
+        assert!(count_recovered(picks) <= 10);
+
+        // Once the window clears 80%, it gets its full share again.
+        for _ in 0..5 {
+            selector.report_result(2, true, Duration::from_millis(10));
+        }
+        let mut picks = Vec::new();
+        for _ in 0..100 {
+            picks.push(selector.select().await.unwrap().id);
+        }
+        assert!(count_recovered(picks) >= 40);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_benches_failing_proxy() {
+        let inner: Arc<dyn ProxySelector> = Arc::new(RoundRobinSelector::new());
+        let selector = DynamicProxySelector::new(inner);
+        selector.set_circuit_breaker(2, Duration::from_millis(50));
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "127.0.0.1:8081"),
+                create_test_proxy(2, "127.0.0.1:8082"),
+            ])
+            .await
+            .unwrap();
+
+        selector.report_failure(2);
+        selector.report_failure(2);
+        for _ in 0..6 {
+            assert_eq!(selector.select().await.unwrap().id, 1);
+        }
+
+        // After the cooldown proxy 2 gets one trial; its success puts it back in rotation
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let mut picks = Vec::new();
+        for _ in 0..4 {
+            picks.push(selector.select().await.unwrap().id);
+        }
+        assert_eq!(picks.iter().filter(|&&id| id == 2).count(), 1);
+
+        selector.report_result(2, true, Duration::from_millis(10));
+        let mut picks = Vec::new();
+        for _ in 0..4 {
+            picks.push(selector.select().await.unwrap().id);
+        }
+        assert_eq!(picks.iter().filter(|&&id| id == 2).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_settings_change_switches_strategy() {
+        let selector = Arc::new(DynamicProxySelector::new(Arc::from(create_selector(
+            RotationStrategy::Random,
+        ))));
+        selector
+            .apply_rotation_settings(&RotationSettings::default())
+            .await
+            .unwrap();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "127.0.0.1:8081"),
+                create_test_proxy(2, "127.0.0.1:8082"),
+            ])
+            .await
+            .unwrap();
+
+        let (settings_tx, settings_rx) = watch::channel(Settings::default());
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let watcher = selector.clone();
+        let task =
+            tokio::spawn(async move { watcher.watch_settings(settings_rx, shutdown_rx).await });
+
+        let mut settings = Settings::default();
+        settings.rotation.method = "least_connections".to_string();
+        settings_tx.send(settings.clone()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while selector.strategy_name() != "least_connections" {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("strategy was never switched");
+        assert_eq!(selector.available_count(), 2);
+
+        // Settings changes that leave the strategy alone don't rebuild the selector
+        settings.rotation.remove_unhealthy = false;
+        assert!(!selector
+            .apply_rotation_settings(&settings.rotation)
+            .await
+            .unwrap());
+        settings.rotation.least_connections_tie_break = "random".to_string();
+        assert!(selector
+            .apply_rotation_settings(&settings.rotation)
+            .await
+            .unwrap());
+
+        shutdown_tx.send(true).unwrap();
+        task.await.unwrap();
+    }
 }
@@ -0,0 +1,260 @@
+//! Latency-weighted proxy selection strategy
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use rand::distributions::{Distribution, WeightedIndex};
+
+use super::{ConnectionTracker, ProxySelector};
+use crate::error::{Result, RotaError};
+use crate::models::Proxy;
+
+/// Smoothing factor for the latency EWMA (weight of the newest sample)
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Lower bound for latencies so a 0ms sample doesn't get infinite weight
+const MIN_LATENCY_MS: f64 = 1.0;
+
+/// Selects proxies with probability inversely proportional to their recent latency
+///
+/// Latency is tracked as an EWMA fed by `report_result`, seeded from the
+/// proxy's stored `avg_response_time`. Proxies without any data get the
+/// median latency of the pool so new proxies still receive traffic.
+pub struct LatencyWeightedSelector {
+    proxies: RwLock<Vec<Arc<Proxy>>>,
+    latencies: DashMap<i64, f64>,
+    tracker: ConnectionTracker,
+}
+
+impl LatencyWeightedSelector {
+    pub fn new() -> Self {
+        Self {
+            proxies: RwLock::new(Vec::new()),
+            latencies: DashMap::new(),
+            tracker: ConnectionTracker::new(),
+        }
+    }
+
+    /// Current latency estimate (in milliseconds) for a proxy, if any
+    pub fn latency_ms(&self, proxy_id: i64) -> Option<f64> {
+        self.latencies.get(&proxy_id).map(|v| *v)
+    }
+
+    fn weights(&self, proxies: &[Arc<Proxy>]) -> Vec<f64> {
+        let known: Vec<Option<f64>> = proxies
+            .iter()
+            .map(|p| self.latency_ms(p.id as i64))
+            .collect();
+
+        let mut sorted: Vec<f64> = known.iter().flatten().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let median = if sorted.is_empty() {
+            MIN_LATENCY_MS
+        } else {
+            sorted[sorted.len() / 2]
+        };
+
+        known
+            .into_iter()
+            .map(|latency| 1.0 / latency.unwrap_or(median).max(MIN_LATENCY_MS))
+            .collect()
+    }
+}
+
+impl Default for LatencyWeightedSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ProxySelector for LatencyWeightedSelector {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
+        let proxies = self.proxies.read();
+
+        if proxies.is_empty() {
+            return Err(RotaError::NoProxiesAvailable);
+        }
+
+        // Ineligible proxies get no weight; the median still comes from the whole pool.
+        let mut weights = self.weights(&proxies);
+        for (weight, proxy) in weights.iter_mut().zip(proxies.iter()) {
+            if !filter(proxy) {
+                *weight = 0.0;
+            }
+        }
+        let dist = WeightedIndex::new(&weights).map_err(|_| RotaError::NoProxiesAvailable)?;
+
+        let mut rng = rand::thread_rng();
+        Ok(proxies[dist.sample(&mut rng)].clone())
+    }
+
+    async fn refresh(&self, proxies: Vec<Proxy>) -> Result<()> {
+        // Seed estimates for proxies we haven't observed yet, drop removed ones.
+        for proxy in &proxies {
+            if proxy.avg_response_time > 0 {
+                self.latencies
+                    .entry(proxy.id as i64)
+                    .or_insert(proxy.avg_response_time as f64);
+            }
+        }
+        self.latencies
+            .retain(|id, _| proxies.iter().any(|p| p.id as i64 == *id));
+
+        let mut guard = self.proxies.write();
+        *guard = proxies.into_iter().map(Arc::new).collect();
+        Ok(())
+    }
+
+    fn available_count(&self) -> usize {
+        self.proxies.read().len()
+    }
+
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.proxies.read().iter().any(|p| p.id as i64 == proxy_id)
+    }
+
+    fn strategy_name(&self) -> &'static str {
+        "latency_weighted"
+    }
+
+    fn acquire(&self, proxy_id: i64) {
+        self.tracker.acquire(proxy_id);
+    }
+
+    fn release(&self, proxy_id: i64) {
+        self.tracker.release(proxy_id);
+    }
+
+    fn active_connections(&self, proxy_id: i64) -> usize {
+        self.tracker.get(proxy_id)
+    }
+
+    fn report_result(&self, proxy_id: i64, success: bool, latency: Duration) {
+        let sample = latency.as_secs_f64() * 1000.0;
+
+        self.latencies
+            .entry(proxy_id)
+            .and_modify(|ewma| {
+                // Failures are penalized as twice the slower of the sample and the estimate.
+                let sample = if success {
+                    sample
+                } else {
+                    sample.max(*ewma) * 2.0
+                };
+                *ewma = EWMA_ALPHA * sample + (1.0 - EWMA_ALPHA) * *ewma;
+            })
+            .or_insert(if success { sample } else { sample * 2.0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_proxy(id: i32, avg_response_time: i32) -> Proxy {
+        Proxy {
+            id,
+            address: format!("127.0.0.1:{}", 8080 + id),
+            protocol: "http".to_string(),
+            username: None,
+            password: None,
+            status: "active".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
+            requests: 0,
+            successful_requests: 0,
+            failed_requests: 0,
+            avg_response_time,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
+            last_error: None,
+            auto_delete_after_failed_seconds: None,
+            invalid_since: None,
+            failure_reasons: serde_json::Value::Array(Vec::new()),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    async fn count_selections(selector: &LatencyWeightedSelector, rounds: usize) -> [usize; 4] {
+        let mut counts = [0usize; 4];
+        for _ in 0..rounds {
+            let proxy = selector.select().await.unwrap();
+            counts[proxy.id as usize] += 1;
+        }
+        counts
+    }
+
+    #[tokio::test]
+    async fn test_latency_weighted_empty() {
+        let selector = LatencyWeightedSelector::new();
+        let result = selector.select().await;
+        assert!(matches!(result, Err(RotaError::NoProxiesAvailable)));
+    }
+
+    #[tokio::test]
+    async fn test_latency_weighted_favors_fast_proxy() {
+        let selector = LatencyWeightedSelector::new();
+        selector
+            .refresh(vec![create_test_proxy(1, 0), create_test_proxy(2, 0)])
+            .await
+            .unwrap();
+
+        for _ in 0..10 {
+            selector.report_result(1, true, Duration::from_millis(50));
+            selector.report_result(2, true, Duration::from_millis(500));
+        }
+
+        let counts = count_selections(&selector, 2000).await;
+        assert!(
+            counts[1] > counts[2] * 3,
+            "fast proxy should dominate: {:?}",
+            counts
+        );
+    }
+
+    #[tokio::test]
+    async fn test_latency_weighted_failures_penalize() {
+        let selector = LatencyWeightedSelector::new();
+        selector
+            .refresh(vec![create_test_proxy(1, 100), create_test_proxy(2, 100)])
+            .await
+            .unwrap();
+
+        for _ in 0..5 {
+            selector.report_result(2, false, Duration::from_millis(100));
+        }
+
+        assert!(selector.latency_ms(2).unwrap() > selector.latency_ms(1).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_latency_weighted_unknown_proxy_gets_median() {
+        let selector = LatencyWeightedSelector::new();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, 100),
+                create_test_proxy(2, 300),
+                create_test_proxy(3, 0),
+            ])
+            .await
+            .unwrap();
+
+        let proxies = selector.proxies.read().clone();
+        let weights = selector.weights(&proxies);
+        assert_eq!(weights[2], 1.0 / 300.0);
+    }
+}
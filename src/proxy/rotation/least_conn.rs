@@ -2,27 +2,84 @@
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use super::{ConnectionTracker, ProxySelector};
 use crate::error::{Result, RotaError};
 use crate::models::Proxy;
 
+/// How to choose among proxies tied for the fewest connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// First tied proxy in list order
+    #[default]
+    First,
+    /// Tied proxy with the lowest average response time
+    LowestLatency,
+    /// Cycle through the tied proxies
+    RoundRobin,
+    /// Random tied proxy
+    Random,
+}
+
+impl TieBreak {
+    pub fn parse(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "latency" | "lowest_latency" | "lowest-latency" => Self::LowestLatency,
+            "roundrobin" | "round_robin" | "round-robin" => Self::RoundRobin,
+            "random" => Self::Random,
+            _ => Self::First,
+        }
+    }
+}
+
 /// Selects the proxy with the fewest active connections
 ///
-/// This strategy helps distribute load evenly across proxies.
+/// This strategy helps distribute load evenly across proxies. Proxies at the
+/// per-proxy connection cap are skipped.
 pub struct LeastConnectionsSelector {
     proxies: RwLock<Vec<Arc<Proxy>>>,
     tracker: ConnectionTracker,
+    tie_break: TieBreak,
+    tie_cursor: AtomicUsize,
 }
 
 impl LeastConnectionsSelector {
     pub fn new() -> Self {
+        Self::with_tie_break(TieBreak::default())
+    }
+
+    pub fn with_tie_break(tie_break: TieBreak) -> Self {
+        Self::with_options(tie_break, 0)
+    }
+
+    /// Create a selector that never picks a proxy already holding
+    /// `max_connections_per_proxy` connections (0 = unlimited)
+    pub fn with_options(tie_break: TieBreak, max_connections_per_proxy: usize) -> Self {
         Self {
             proxies: RwLock::new(Vec::new()),
-            tracker: ConnectionTracker::new(),
+            tracker: ConnectionTracker::with_max_connections_per_proxy(max_connections_per_proxy),
+            tie_break,
+            tie_cursor: AtomicUsize::new(0),
         }
     }
+
+    fn break_tie(&self, tied: &[&Arc<Proxy>]) -> Option<Arc<Proxy>> {
+        let proxy = match self.tie_break {
+            TieBreak::First => tied.first(),
+            // Proxies without a measured response time rank after measured ones.
+            TieBreak::LowestLatency => tied
+                .iter()
+                .min_by_key(|p| (p.avg_response_time <= 0, p.avg_response_time)),
+            TieBreak::RoundRobin => {
+                tied.get(self.tie_cursor.fetch_add(1, Ordering::Relaxed) % tied.len().max(1))
+            }
+            TieBreak::Random => tied.choose(&mut rand::thread_rng()),
+        };
+        proxy.map(|p| Arc::clone(p))
+    }
 }
 
 impl Default for LeastConnectionsSelector {
@@ -33,26 +90,35 @@ impl Default for LeastConnectionsSelector {
 
 #[async_trait]
 impl ProxySelector for LeastConnectionsSelector {
-    async fn select(&self) -> Result<Arc<Proxy>> {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
         let proxies = self.proxies.read();
 
         if proxies.is_empty() {
             return Err(RotaError::NoProxiesAvailable);
         }
 
-        // Find the proxy with the least connections
+        // Collect the eligible, unsaturated proxies sharing the least connections
         let mut min_connections = usize::MAX;
-        let mut selected: Option<Arc<Proxy>> = None;
+        let mut tied: Vec<&Arc<Proxy>> = Vec::new();
 
         for proxy in proxies.iter() {
+            if self.tracker.is_saturated(proxy.id as i64) || !filter(proxy) {
+                continue;
+            }
             let connections = self.tracker.get(proxy.id as i64);
             if connections < min_connections {
                 min_connections = connections;
-                selected = Some(proxy.clone());
+                tied.clear();
+            }
+            if connections == min_connections {
+                tied.push(proxy);
             }
         }
 
-        selected.ok_or(RotaError::NoProxiesAvailable)
+        self.break_tie(&tied).ok_or(RotaError::NoProxiesAvailable)
     }
 
     async fn refresh(&self, proxies: Vec<Proxy>) -> Result<()> {
@@ -66,10 +132,18 @@ impl ProxySelector for LeastConnectionsSelector {
         self.proxies.read().len()
     }
 
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.proxies.read().iter().any(|p| p.id as i64 == proxy_id)
+    }
+
     fn strategy_name(&self) -> &'static str {
         "least_connections"
     }
 
+    fn is_saturated(&self, proxy_id: i64) -> bool {
+        self.tracker.is_saturated(proxy_id)
+    }
+
     fn acquire(&self, proxy_id: i64) {
         self.tracker.acquire(proxy_id);
     }
@@ -77,6 +151,10 @@ impl ProxySelector for LeastConnectionsSelector {
     fn release(&self, proxy_id: i64) {
         self.tracker.release(proxy_id);
     }
+
+    fn active_connections(&self, proxy_id: i64) -> usize {
+        self.tracker.get(proxy_id)
+    }
 }
 
 #[cfg(test)]
@@ -91,11 +169,20 @@ mod tests {
             username: None,
             password: None,
             status: "idle".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
             requests: 0,
             successful_requests: 0,
             failed_requests: 0,
             avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
             last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
             last_error: None,
             auto_delete_after_failed_seconds: None,
             invalid_since: None,
@@ -143,6 +230,29 @@ mod tests {
         assert_eq!(selected.id, 2);
     }
 
+    #[tokio::test]
+    async fn test_least_conn_select_matching_skips_excluded_least_loaded() {
+        let selector = LeastConnectionsSelector::new();
+        let proxies = vec![
+            create_test_proxy(1, "proxy1"),
+            create_test_proxy(2, "proxy2"),
+            create_test_proxy(3, "proxy3"),
+        ];
+        selector.refresh(proxies).await.unwrap();
+        selector.acquire(2);
+        selector.acquire(3);
+        selector.acquire(3);
+
+        // Proxy 1 is the least loaded but excluded, so the next least loaded is picked
+        for _ in 0..3 {
+            let selected = selector.select_matching(&|p| p.id != 1).await.unwrap();
+            assert_eq!(selected.id, 2);
+        }
+
+        let result = selector.select_matching(&|p| p.id > 3).await;
+        assert!(matches!(result, Err(RotaError::NoProxiesAvailable)));
+    }
+
     #[tokio::test]
     async fn test_least_conn_release() {
         let selector = LeastConnectionsSelector::new();
@@ -168,4 +278,67 @@ mod tests {
         // Now proxy1 should be selected (0 connections, comes first)
         assert_eq!(selector.select().await.unwrap().id, 1);
     }
+
+    #[tokio::test]
+    async fn test_ties_follow_configured_policy() {
+        let mut proxies = vec![
+            create_test_proxy(1, "proxy1"),
+            create_test_proxy(2, "proxy2"),
+            create_test_proxy(3, "proxy3"),
+        ];
+        proxies[0].avg_response_time = 300;
+        proxies[1].avg_response_time = 0;
+        proxies[2].avg_response_time = 120;
+
+        let selector = LeastConnectionsSelector::with_tie_break(TieBreak::LowestLatency);
+        selector.refresh(proxies.clone()).await.unwrap();
+        assert_eq!(selector.select().await.unwrap().id, 3);
+
+        let selector = LeastConnectionsSelector::with_tie_break(TieBreak::RoundRobin);
+        selector.refresh(proxies).await.unwrap();
+        selector.acquire(2);
+        let mut picks = Vec::new();
+        for _ in 0..4 {
+            picks.push(selector.select().await.unwrap().id);
+        }
+        assert_eq!(picks, vec![1, 3, 1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_saturated_proxies_are_skipped() {
+        let selector = LeastConnectionsSelector::with_options(TieBreak::First, 2);
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "proxy1"),
+                create_test_proxy(2, "proxy2"),
+            ])
+            .await
+            .unwrap();
+
+        selector.acquire(1);
+        selector.acquire(1);
+        selector.acquire(2);
+        selector.acquire(2);
+        selector.release(2);
+
+        // Proxy 1 is at the cap; proxy 2 has room for one more
+        for _ in 0..3 {
+            assert_eq!(selector.select().await.unwrap().id, 2);
+        }
+
+        selector.acquire(2);
+        let result = selector.select().await;
+        assert!(matches!(result, Err(RotaError::NoProxiesAvailable)));
+
+        selector.release(1);
+        assert_eq!(selector.select().await.unwrap().id, 1);
+    }
+
+    #[test]
+    fn test_tie_break_parse() {
+        assert_eq!(TieBreak::parse("round-robin"), TieBreak::RoundRobin);
+        assert_eq!(TieBreak::parse("latency"), TieBreak::LowestLatency);
+        assert_eq!(TieBreak::parse("random"), TieBreak::Random);
+        assert_eq!(TieBreak::parse(""), TieBreak::First);
+    }
 }
@@ -2,32 +2,47 @@
 //!
 //! This module provides various strategies for selecting proxies from the pool.
 
+mod breaker;
+mod composite;
 mod dynamic;
+mod latency_weighted;
 mod least_conn;
 mod random;
+mod recovery;
 mod round_robin;
+mod sticky;
 mod time_based;
+mod weighted_random;
 
+pub use composite::{CompositeSelector, CompositeWeights};
 pub use dynamic::DynamicProxySelector;
-pub use least_conn::LeastConnectionsSelector;
+pub use latency_weighted::LatencyWeightedSelector;
+pub use least_conn::{LeastConnectionsSelector, TieBreak};
 pub use random::RandomSelector;
 pub use round_robin::RoundRobinSelector;
+pub use sticky::StickyUntilFailureSelector;
 pub use time_based::TimeBasedSelector;
+pub use weighted_random::WeightedRandomSelector;
 
 use async_trait::async_trait;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::error::Result;
 use crate::models::Proxy;
 
 /// Strategy types for proxy rotation
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum RotationStrategy {
     #[default]
     Random,
     RoundRobin,
     LeastConnections,
     TimeBased,
+    LatencyWeighted,
+    StickyUntilFailure,
+    WeightedRandom,
+    Composite,
 }
 
 impl RotationStrategy {
@@ -38,6 +53,16 @@ impl RotationStrategy {
                 Self::LeastConnections
             }
             "time_based" | "timebased" | "time-based" => Self::TimeBased,
+            "latency_weighted" | "latencyweighted" | "latency-weighted" | "latency" => {
+                Self::LatencyWeighted
+            }
+            "sticky_until_failure" | "stickyuntilfailure" | "sticky-until-failure" | "sticky" => {
+                Self::StickyUntilFailure
+            }
+            "weighted_random" | "weightedrandom" | "weighted-random" | "weighted" => {
+                Self::WeightedRandom
+            }
+            "composite" | "weighted_score" | "weighted-score" | "score" => Self::Composite,
             _ => Self::Random,
         }
     }
@@ -48,6 +73,10 @@ impl RotationStrategy {
             Self::RoundRobin => "round_robin",
             Self::LeastConnections => "least_connections",
             Self::TimeBased => "time_based",
+            Self::LatencyWeighted => "latency_weighted",
+            Self::StickyUntilFailure => "sticky_until_failure",
+            Self::WeightedRandom => "weighted_random",
+            Self::Composite => "composite",
         }
     }
 }
@@ -61,7 +90,18 @@ pub trait ProxySelector: Send + Sync {
     /// Select a proxy from the available pool
     ///
     /// Returns an error if no proxies are available
-    async fn select(&self) -> Result<Arc<Proxy>>;
+    async fn select(&self) -> Result<Arc<Proxy>> {
+        self.select_matching(&|_| true).await
+    }
+
+    /// Select a proxy that satisfies `filter`
+    ///
+    /// The strategy picks among the matching proxies only, so ineligible proxies never
+    /// count as a failed attempt. Returns `NoProxiesAvailable` if none match.
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>>;
 
     /// Refresh the internal proxy list
     ///
@@ -71,6 +111,13 @@ pub trait ProxySelector: Send + Sync {
     /// Get the number of available proxies
     fn available_count(&self) -> usize;
 
+    /// Whether the proxy is still in the pool as of the latest refresh
+    ///
+    /// Selectors that don't keep a proxy list report every proxy as present.
+    fn contains(&self, _proxy_id: i64) -> bool {
+        true
+    }
+
     /// Get the strategy name
     fn strategy_name(&self) -> &'static str;
 
@@ -79,6 +126,34 @@ pub trait ProxySelector: Send + Sync {
 
     /// Mark a proxy as no longer being used
     fn release(&self, proxy_id: i64);
+
+    /// Connections currently held through a proxy
+    ///
+    /// Selectors that don't track connections report 0.
+    fn active_connections(&self, _proxy_id: i64) -> usize {
+        0
+    }
+
+    /// Report the outcome of a request made through a proxy
+    ///
+    /// Strategies that adapt to live performance override this; the default ignores it.
+    fn report_result(&self, _proxy_id: i64, _success: bool, _latency: Duration) {}
+
+    /// Whether the proxy holds as many connections as the strategy allows it
+    fn is_saturated(&self, _proxy_id: i64) -> bool {
+        false
+    }
+
+    /// Report a failed request, counted towards the proxy's circuit breaker
+    fn report_failure(&self, _proxy_id: i64) {}
+
+    /// Called when a health check brings a failed proxy back
+    fn mark_recovered(&self, _proxy_id: i64) {}
+
+    /// Selector to use for requests to `host`, if a host rule overrides the default
+    fn selector_for_host(&self, _host: &str) -> Option<Arc<dyn ProxySelector>> {
+        None
+    }
 }
 
 /// Connection tracker for proxies
@@ -87,12 +162,19 @@ pub trait ProxySelector: Send + Sync {
 #[derive(Debug, Default)]
 pub struct ConnectionTracker {
     connections: dashmap::DashMap<i64, usize>,
+    /// Active connections at which a proxy counts as saturated (0 = unlimited)
+    max_connections_per_proxy: usize,
 }
 
 impl ConnectionTracker {
     pub fn new() -> Self {
+        Self::with_max_connections_per_proxy(0)
+    }
+
+    pub fn with_max_connections_per_proxy(max_connections_per_proxy: usize) -> Self {
         Self {
             connections: dashmap::DashMap::new(),
+            max_connections_per_proxy,
         }
     }
 
@@ -115,6 +197,11 @@ impl ConnectionTracker {
         self.connections.get(&proxy_id).map(|v| *v).unwrap_or(0)
     }
 
+    /// Whether the proxy already holds its maximum number of connections
+    pub fn is_saturated(&self, proxy_id: i64) -> bool {
+        self.max_connections_per_proxy > 0 && self.get(proxy_id) >= self.max_connections_per_proxy
+    }
+
     pub fn clear(&self) {
         self.connections.clear();
     }
@@ -127,12 +214,17 @@ pub fn create_selector(strategy: RotationStrategy) -> Box<dyn ProxySelector> {
         RotationStrategy::RoundRobin => Box::new(RoundRobinSelector::new()),
         RotationStrategy::LeastConnections => Box::new(LeastConnectionsSelector::new()),
         RotationStrategy::TimeBased => Box::new(TimeBasedSelector::new()),
+        RotationStrategy::LatencyWeighted => Box::new(LatencyWeightedSelector::new()),
+        RotationStrategy::StickyUntilFailure => Box::new(StickyUntilFailureSelector::new()),
+        RotationStrategy::WeightedRandom => Box::new(WeightedRandomSelector::new()),
+        RotationStrategy::Composite => Box::new(CompositeSelector::new()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::RotaError;
 
     #[test]
     fn test_rotation_strategy_from_str() {
@@ -152,6 +244,22 @@ mod tests {
             RotationStrategy::from_str("timebased"),
             RotationStrategy::TimeBased
         );
+        assert_eq!(
+            RotationStrategy::from_str("latency-weighted"),
+            RotationStrategy::LatencyWeighted
+        );
+        assert_eq!(
+            RotationStrategy::from_str("sticky"),
+            RotationStrategy::StickyUntilFailure
+        );
+        assert_eq!(
+            RotationStrategy::from_str("weighted-random"),
+            RotationStrategy::WeightedRandom
+        );
+        assert_eq!(
+            RotationStrategy::from_str("weighted-score"),
+            RotationStrategy::Composite
+        );
         assert_eq!(
             RotationStrategy::from_str("unknown"),
             RotationStrategy::Random
@@ -167,6 +275,16 @@ mod tests {
             "least_connections"
         );
         assert_eq!(RotationStrategy::TimeBased.as_str(), "time_based");
+        assert_eq!(
+            RotationStrategy::LatencyWeighted.as_str(),
+            "latency_weighted"
+        );
+        assert_eq!(
+            RotationStrategy::StickyUntilFailure.as_str(),
+            "sticky_until_failure"
+        );
+        assert_eq!(RotationStrategy::WeightedRandom.as_str(), "weighted_random");
+        assert_eq!(RotationStrategy::Composite.as_str(), "composite");
     }
 
     #[test]
@@ -187,6 +305,92 @@ mod tests {
             create_selector(RotationStrategy::TimeBased).strategy_name(),
             "time_based"
         );
+        assert_eq!(
+            create_selector(RotationStrategy::LatencyWeighted).strategy_name(),
+            "latency_weighted"
+        );
+        assert_eq!(
+            create_selector(RotationStrategy::StickyUntilFailure).strategy_name(),
+            "sticky_until_failure"
+        );
+        assert_eq!(
+            create_selector(RotationStrategy::WeightedRandom).strategy_name(),
+            "weighted_random"
+        );
+        assert_eq!(
+            create_selector(RotationStrategy::Composite).strategy_name(),
+            "composite"
+        );
+    }
+
+    fn create_test_proxy(id: i32, protocol: &str) -> Proxy {
+        Proxy {
+            id,
+            address: format!("127.0.0.1:{}", 8080 + id),
+            protocol: protocol.to_string(),
+            username: None,
+            password: None,
+            status: "active".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
+            requests: 0,
+            successful_requests: 0,
+            failed_requests: 0,
+            avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
+            last_error: None,
+            auto_delete_after_failed_seconds: None,
+            invalid_since: None,
+            failure_reasons: serde_json::Value::Array(Vec::new()),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_select_matching_skips_ineligible_proxies() {
+        let selector = RoundRobinSelector::new();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "http"),
+                create_test_proxy(2, "socks5"),
+                create_test_proxy(3, "http"),
+            ])
+            .await
+            .unwrap();
+
+        for _ in 0..6 {
+            let proxy = selector
+                .select_matching(&|p: &Proxy| p.protocol != "http")
+                .await
+                .unwrap();
+            assert_eq!(proxy.id, 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_select_matching_no_eligible_proxy() {
+        let selector = RoundRobinSelector::new();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, "http"),
+                create_test_proxy(2, "http"),
+            ])
+            .await
+            .unwrap();
+
+        let err = selector
+            .select_matching(&|p: &Proxy| p.protocol == "socks5")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RotaError::NoProxiesAvailable));
     }
 
     #[test]
@@ -209,4 +413,25 @@ mod tests {
         tracker.clear();
         assert_eq!(tracker.get(1), 0);
     }
+
+    #[test]
+    fn test_connection_tracker_saturation() {
+        let tracker = ConnectionTracker::with_max_connections_per_proxy(2);
+
+        tracker.acquire(1);
+        assert!(!tracker.is_saturated(1));
+        tracker.acquire(1);
+        assert!(tracker.is_saturated(1));
+        assert!(!tracker.is_saturated(2));
+
+        tracker.release(1);
+        assert!(!tracker.is_saturated(1));
+
+        // Without a cap no proxy is ever saturated
+        let tracker = ConnectionTracker::new();
+        for _ in 0..100 {
+            tracker.acquire(1);
+        }
+        assert!(!tracker.is_saturated(1));
+    }
 }
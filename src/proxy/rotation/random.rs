@@ -32,17 +32,17 @@ impl Default for RandomSelector {
 
 #[async_trait]
 impl ProxySelector for RandomSelector {
-    async fn select(&self) -> Result<Arc<Proxy>> {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
         let proxies = self.proxies.read();
-
-        if proxies.is_empty() {
-            return Err(RotaError::NoProxiesAvailable);
-        }
+        let eligible: Vec<&Arc<Proxy>> = proxies.iter().filter(|p| filter(p)).collect();
 
         let mut rng = rand::thread_rng();
-        proxies
+        eligible
             .choose(&mut rng)
-            .cloned()
+            .map(|p| Arc::clone(p))
             .ok_or(RotaError::NoProxiesAvailable)
     }
 
@@ -56,6 +56,10 @@ impl ProxySelector for RandomSelector {
         self.proxies.read().len()
     }
 
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.proxies.read().iter().any(|p| p.id as i64 == proxy_id)
+    }
+
     fn strategy_name(&self) -> &'static str {
         "random"
     }
@@ -81,11 +85,20 @@ mod tests {
             username: None,
             password: None,
             status: "idle".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
             requests: 0,
             successful_requests: 0,
             failed_requests: 0,
             avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
             last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
             last_error: None,
             auto_delete_after_failed_seconds: None,
             invalid_since: None,
@@ -0,0 +1,110 @@
+//! Probation for proxies that just recovered from failure
+//!
+//! A recovered proxy only gets probe traffic until its recent success rate
+//! clears the configured threshold.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+
+/// One in this many selections may pick a proxy on probation
+const PROBE_EVERY: u64 = 10;
+
+#[derive(Debug, Clone, Copy)]
+struct GateSettings {
+    /// Success rate percentage a recovered proxy must reach (0 = gate off)
+    min_success_rate: f64,
+    /// Number of recent results the success rate is computed over
+    window: usize,
+}
+
+/// Tracks recovered proxies and the recent results they need to prove out
+pub struct RecoveryGate {
+    settings: RwLock<GateSettings>,
+    probation: DashMap<i64, VecDeque<bool>>,
+    selections: AtomicU64,
+}
+
+impl RecoveryGate {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(GateSettings {
+                min_success_rate: 0.0,
+                window: 10,
+            }),
+            probation: DashMap::new(),
+            selections: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the success rate (percent, 0 = off) recovered proxies need over `window` results
+    pub fn configure(&self, min_success_rate: f64, window: usize) {
+        *self.settings.write() = GateSettings {
+            min_success_rate,
+            window: window.max(1),
+        };
+        if min_success_rate <= 0.0 {
+            self.probation.clear();
+        }
+    }
+
+    /// Put a proxy that just recovered on probation
+    pub fn start(&self, proxy_id: i64) {
+        if self.settings.read().min_success_rate > 0.0 {
+            self.probation.insert(proxy_id, VecDeque::new());
+        }
+    }
+
+    /// Record a result; the proxy leaves probation once its window clears the threshold
+    pub fn record(&self, proxy_id: i64, success: bool) {
+        let settings = *self.settings.read();
+        let proven = match self.probation.get_mut(&proxy_id) {
+            Some(mut results) => {
+                results.push_back(success);
+                while results.len() > settings.window {
+                    results.pop_front();
+                }
+                let successes = results.iter().filter(|&&ok| ok).count();
+                results.len() == settings.window
+                    && successes as f64 * 100.0 / settings.window as f64
+                        >= settings.min_success_rate
+            }
+            None => return,
+        };
+
+        if proven {
+            self.probation.remove(&proxy_id);
+        }
+    }
+
+    pub fn is_on_probation(&self, proxy_id: i64) -> bool {
+        self.probation.contains_key(&proxy_id)
+    }
+
+    /// Whether this selection may go to a proxy on probation
+    pub fn probe_turn(&self) -> bool {
+        self.probation.is_empty()
+            || self
+                .selections
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(PROBE_EVERY)
+    }
+
+    /// Forget every proxy on probation
+    pub fn clear(&self) {
+        self.probation.clear();
+    }
+
+    /// Forget proxies that left the pool
+    pub fn retain(&self, proxy_ids: &[i64]) {
+        self.probation.retain(|id, _| proxy_ids.contains(id));
+    }
+}
+
+impl Default for RecoveryGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -36,20 +36,24 @@ impl Default for RoundRobinSelector {
 
 #[async_trait]
 impl ProxySelector for RoundRobinSelector {
-    async fn select(&self) -> Result<Arc<Proxy>> {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
         let proxies = self.proxies.read();
+        let eligible: Vec<&Arc<Proxy>> = proxies.iter().filter(|p| filter(p)).collect();
 
-        if proxies.is_empty() {
+        if eligible.is_empty() {
             return Err(RotaError::NoProxiesAvailable);
         }
 
-        let len = proxies.len();
+        let len = eligible.len();
         // Atomically increment and get the previous value, then wrap around
         let idx = self.index.fetch_add(1, Ordering::Relaxed) % len;
 
-        proxies
+        eligible
             .get(idx)
-            .cloned()
+            .map(|p| Arc::clone(p))
             .ok_or(RotaError::NoProxiesAvailable)
     }
 
@@ -65,6 +69,10 @@ impl ProxySelector for RoundRobinSelector {
         self.proxies.read().len()
     }
 
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.proxies.read().iter().any(|p| p.id as i64 == proxy_id)
+    }
+
     fn strategy_name(&self) -> &'static str {
         "round_robin"
     }
@@ -90,11 +98,20 @@ mod tests {
             username: None,
             password: None,
             status: "idle".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
             requests: 0,
             successful_requests: 0,
             failed_requests: 0,
             avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
             last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
             last_error: None,
             auto_delete_after_failed_seconds: None,
             invalid_since: None,
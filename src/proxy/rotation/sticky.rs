@@ -0,0 +1,228 @@
+//! Sticky-until-failure proxy selection strategy
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{ProxySelector, RoundRobinSelector};
+use crate::error::Result;
+use crate::models::Proxy;
+
+/// Keeps returning the same proxy until a failure is reported for it
+///
+/// When the current proxy fails, the next one is picked by the inner
+/// strategy (round-robin by default), which is asked to skip the proxy that
+/// just failed.
+pub struct StickyUntilFailureSelector {
+    inner: Box<dyn ProxySelector>,
+    current: RwLock<Option<Arc<Proxy>>>,
+    last_failed: RwLock<Option<i64>>,
+}
+
+impl StickyUntilFailureSelector {
+    pub fn new() -> Self {
+        Self::with_inner(Box::new(RoundRobinSelector::new()))
+    }
+
+    /// Use `inner` to pick the next proxy after a failure
+    pub fn with_inner(inner: Box<dyn ProxySelector>) -> Self {
+        Self {
+            inner,
+            current: RwLock::new(None),
+            last_failed: RwLock::new(None),
+        }
+    }
+
+    /// ID of the proxy currently being stuck to, if any
+    pub fn current_id(&self) -> Option<i64> {
+        self.current.read().as_ref().map(|p| p.id as i64)
+    }
+}
+
+impl Default for StickyUntilFailureSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ProxySelector for StickyUntilFailureSelector {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
+        let current = self.current.read().clone();
+        match current {
+            Some(proxy) if filter(&proxy) => return Ok(proxy),
+            // Stay stuck to the current proxy for requests it can serve
+            Some(_) => return self.inner.select_matching(filter).await,
+            None => {}
+        }
+
+        let failed = *self.last_failed.read();
+        let proxy = match failed {
+            Some(failed_id) => match self
+                .inner
+                .select_matching(&|p| p.id as i64 != failed_id && filter(p))
+                .await
+            {
+                Ok(proxy) => proxy,
+                // The failed proxy is the only one left
+                Err(_) => self.inner.select_matching(filter).await?,
+            },
+            None => self.inner.select_matching(filter).await?,
+        };
+
+        // Another request may have picked a proxy concurrently; keep the first one.
+        let mut current = self.current.write();
+        Ok(current.get_or_insert(proxy).clone())
+    }
+
+    async fn refresh(&self, proxies: Vec<Proxy>) -> Result<()> {
+        {
+            let mut current = self.current.write();
+            *current = current
+                .as_ref()
+                .and_then(|c| proxies.iter().find(|p| p.id == c.id))
+                .map(|p| Arc::new(p.clone()));
+        }
+        self.inner.refresh(proxies).await
+    }
+
+    fn available_count(&self) -> usize {
+        self.inner.available_count()
+    }
+
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.inner.contains(proxy_id)
+    }
+
+    fn strategy_name(&self) -> &'static str {
+        "sticky_until_failure"
+    }
+
+    fn acquire(&self, proxy_id: i64) {
+        self.inner.acquire(proxy_id);
+    }
+
+    fn release(&self, proxy_id: i64) {
+        self.inner.release(proxy_id);
+    }
+
+    fn report_result(&self, proxy_id: i64, success: bool, latency: Duration) {
+        self.inner.report_result(proxy_id, success, latency);
+
+        if success {
+            return;
+        }
+
+        let mut current = self.current.write();
+        if current.as_ref().is_some_and(|p| p.id as i64 == proxy_id) {
+            *current = None;
+            *self.last_failed.write() = Some(proxy_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RotaError;
+
+    fn create_test_proxy(id: i32) -> Proxy {
+        Proxy {
+            id,
+            address: format!("127.0.0.1:{}", 8080 + id),
+            protocol: "http".to_string(),
+            username: None,
+            password: None,
+            status: "active".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
+            requests: 0,
+            successful_requests: 0,
+            failed_requests: 0,
+            avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
+            last_error: None,
+            auto_delete_after_failed_seconds: None,
+            invalid_since: None,
+            failure_reasons: serde_json::Value::Array(Vec::new()),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sticky_empty() {
+        let selector = StickyUntilFailureSelector::new();
+        let result = selector.select().await;
+        assert!(matches!(result, Err(RotaError::NoProxiesAvailable)));
+    }
+
+    #[tokio::test]
+    async fn test_sticky_stays_through_successes() {
+        let selector = StickyUntilFailureSelector::new();
+        selector
+            .refresh(vec![create_test_proxy(1), create_test_proxy(2)])
+            .await
+            .unwrap();
+
+        let first = selector.select().await.unwrap();
+        for _ in 0..10 {
+            selector.report_result(first.id as i64, true, Duration::from_millis(10));
+            assert_eq!(selector.select().await.unwrap().id, first.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sticky_advances_on_failure() {
+        let selector = StickyUntilFailureSelector::new();
+        selector
+            .refresh(vec![
+                create_test_proxy(1),
+                create_test_proxy(2),
+                create_test_proxy(3),
+            ])
+            .await
+            .unwrap();
+
+        let first = selector.select().await.unwrap();
+        selector.report_result(first.id as i64, false, Duration::from_millis(10));
+
+        let second = selector.select().await.unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(selector.select().await.unwrap().id, second.id);
+
+        // Failures reported for other proxies don't move us off the current one
+        selector.report_result(first.id as i64, false, Duration::from_millis(10));
+        assert_eq!(selector.select().await.unwrap().id, second.id);
+    }
+
+    #[tokio::test]
+    async fn test_sticky_refresh_drops_removed_proxy() {
+        let selector = StickyUntilFailureSelector::new();
+        selector
+            .refresh(vec![create_test_proxy(1), create_test_proxy(2)])
+            .await
+            .unwrap();
+
+        let first = selector.select().await.unwrap();
+        let remaining = if first.id == 1 { 2 } else { 1 };
+        selector
+            .refresh(vec![create_test_proxy(remaining)])
+            .await
+            .unwrap();
+
+        assert_eq!(selector.current_id(), None);
+        assert_eq!(selector.select().await.unwrap().id, remaining);
+    }
+}
@@ -82,7 +82,10 @@ impl Default for TimeBasedSelector {
 
 #[async_trait]
 impl ProxySelector for TimeBasedSelector {
-    async fn select(&self) -> Result<Arc<Proxy>> {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
         let proxies = self.proxies.read();
 
         if proxies.is_empty() {
@@ -92,9 +95,11 @@ impl ProxySelector for TimeBasedSelector {
         // Check if we need to rotate
         self.maybe_rotate(proxies.len());
 
+        // The current proxy, or the next matching one after it without rotating to it
         let index = *self.current_index.read();
-        proxies
-            .get(index)
+        (0..proxies.len())
+            .filter_map(|offset| proxies.get((index + offset) % proxies.len()))
+            .find(|p| filter(p))
             .cloned()
             .ok_or(RotaError::NoProxiesAvailable)
     }
@@ -119,6 +124,10 @@ impl ProxySelector for TimeBasedSelector {
         self.proxies.read().len()
     }
 
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.proxies.read().iter().any(|p| p.id as i64 == proxy_id)
+    }
+
     fn strategy_name(&self) -> &'static str {
         "time_based"
     }
@@ -130,6 +139,10 @@ impl ProxySelector for TimeBasedSelector {
     fn release(&self, proxy_id: i64) {
         self.tracker.release(proxy_id);
     }
+
+    fn active_connections(&self, proxy_id: i64) -> usize {
+        self.tracker.get(proxy_id)
+    }
 }
 
 #[cfg(test)]
@@ -145,11 +158,20 @@ mod tests {
             username: None,
             password: None,
             status: "idle".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
             requests: 0,
             successful_requests: 0,
             failed_requests: 0,
             avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
             last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
             last_error: None,
             auto_delete_after_failed_seconds: None,
             invalid_since: None,
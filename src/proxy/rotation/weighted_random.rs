@@ -0,0 +1,249 @@
+//! Weighted random proxy selection strategy
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use rand::distributions::{Distribution, WeightedIndex};
+
+use super::{ConnectionTracker, ProxySelector};
+use crate::error::{Result, RotaError};
+use crate::models::Proxy;
+
+/// Response time (ms) at which a proxy's weight is halved
+const REFERENCE_LATENCY_MS: f64 = 1000.0;
+
+/// Lower bound for a proxy's weight so failing proxies still get occasional traffic
+const MIN_WEIGHT: f64 = 0.01;
+
+/// Proxies and their selection distribution, rebuilt on refresh
+#[derive(Default)]
+struct WeightedPool {
+    proxies: Vec<Arc<Proxy>>,
+    weights: Vec<f64>,
+    dist: Option<WeightedIndex<f64>>,
+}
+
+/// Selects proxies with probability proportional to their success rate and speed
+///
+/// Weights are computed from the stored `success_rate()` and `avg_response_time` when
+/// the pool is refreshed. Proxies without any requests get the median weight of the
+/// pool so new proxies still receive traffic.
+pub struct WeightedRandomSelector {
+    pool: RwLock<WeightedPool>,
+    tracker: ConnectionTracker,
+}
+
+impl WeightedRandomSelector {
+    pub fn new() -> Self {
+        Self {
+            pool: RwLock::new(WeightedPool::default()),
+            tracker: ConnectionTracker::new(),
+        }
+    }
+
+    /// Weight of a proxy with request history, `None` for proxies without any
+    fn measured_weight(proxy: &Proxy) -> Option<f64> {
+        if proxy.requests == 0 {
+            return None;
+        }
+
+        let success = proxy.success_rate() / 100.0;
+        let latency = proxy.avg_response_time.max(0) as f64;
+        let speed = REFERENCE_LATENCY_MS / (REFERENCE_LATENCY_MS + latency);
+        Some((success * speed).max(MIN_WEIGHT))
+    }
+
+    fn weights(proxies: &[Arc<Proxy>]) -> Vec<f64> {
+        let measured: Vec<Option<f64>> = proxies.iter().map(|p| Self::measured_weight(p)).collect();
+
+        let mut sorted: Vec<f64> = measured.iter().flatten().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let neutral = if sorted.is_empty() {
+            1.0
+        } else {
+            sorted[sorted.len() / 2]
+        };
+
+        measured
+            .into_iter()
+            .map(|weight| weight.unwrap_or(neutral))
+            .collect()
+    }
+}
+
+impl Default for WeightedRandomSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ProxySelector for WeightedRandomSelector {
+    async fn select_matching(
+        &self,
+        filter: &(dyn for<'p> Fn(&'p Proxy) -> bool + Send + Sync),
+    ) -> Result<Arc<Proxy>> {
+        let pool = self.pool.read();
+        let dist = pool.dist.as_ref().ok_or(RotaError::NoProxiesAvailable)?;
+
+        let mut rng = rand::thread_rng();
+        let proxy = &pool.proxies[dist.sample(&mut rng)];
+        if filter(proxy) {
+            return Ok(proxy.clone());
+        }
+
+        // Draw again among the matching proxies only
+        let weights = pool
+            .proxies
+            .iter()
+            .zip(&pool.weights)
+            .map(|(proxy, weight)| if filter(proxy) { *weight } else { 0.0 });
+        let dist = WeightedIndex::new(weights).map_err(|_| RotaError::NoProxiesAvailable)?;
+        Ok(pool.proxies[dist.sample(&mut rng)].clone())
+    }
+
+    async fn refresh(&self, proxies: Vec<Proxy>) -> Result<()> {
+        let proxies: Vec<Arc<Proxy>> = proxies.into_iter().map(Arc::new).collect();
+        let weights = Self::weights(&proxies);
+        let dist = WeightedIndex::new(&weights).ok();
+
+        *self.pool.write() = WeightedPool {
+            proxies,
+            weights,
+            dist,
+        };
+        Ok(())
+    }
+
+    fn available_count(&self) -> usize {
+        self.pool.read().proxies.len()
+    }
+
+    fn contains(&self, proxy_id: i64) -> bool {
+        self.pool
+            .read()
+            .proxies
+            .iter()
+            .any(|p| p.id as i64 == proxy_id)
+    }
+
+    fn strategy_name(&self) -> &'static str {
+        "weighted_random"
+    }
+
+    fn acquire(&self, proxy_id: i64) {
+        self.tracker.acquire(proxy_id);
+    }
+
+    fn release(&self, proxy_id: i64) {
+        self.tracker.release(proxy_id);
+    }
+
+    fn active_connections(&self, proxy_id: i64) -> usize {
+        self.tracker.get(proxy_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_proxy(id: i32, requests: i64, successful: i64, avg_ms: i32) -> Proxy {
+        Proxy {
+            id,
+            address: format!("127.0.0.1:{}", 8080 + id),
+            protocol: "http".to_string(),
+            username: None,
+            password: None,
+            status: "active".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
+            requests,
+            successful_requests: successful,
+            failed_requests: requests - successful,
+            avg_response_time: avg_ms,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
+            last_error: None,
+            auto_delete_after_failed_seconds: None,
+            invalid_since: None,
+            failure_reasons: serde_json::Value::Array(Vec::new()),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    async fn count_selections(selector: &WeightedRandomSelector, rounds: usize) -> [usize; 4] {
+        let mut counts = [0usize; 4];
+        for _ in 0..rounds {
+            let proxy = selector.select().await.unwrap();
+            counts[proxy.id as usize] += 1;
+        }
+        counts
+    }
+
+    #[tokio::test]
+    async fn test_weighted_random_empty() {
+        let selector = WeightedRandomSelector::new();
+        let result = selector.select().await;
+        assert!(matches!(result, Err(RotaError::NoProxiesAvailable)));
+    }
+
+    #[tokio::test]
+    async fn test_weighted_random_favors_successful_proxy() {
+        let selector = WeightedRandomSelector::new();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, 100, 95, 200),
+                create_test_proxy(2, 100, 20, 200),
+            ])
+            .await
+            .unwrap();
+
+        let counts = count_selections(&selector, 2000).await;
+        assert!(
+            counts[1] > counts[2] * 3,
+            "successful proxy should dominate: {:?}",
+            counts
+        );
+        assert!(counts[2] > 0, "weak proxy should still be picked");
+    }
+
+    #[tokio::test]
+    async fn test_weighted_random_penalizes_slow_proxy() {
+        let selector = WeightedRandomSelector::new();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, 100, 100, 100),
+                create_test_proxy(2, 100, 100, 3000),
+            ])
+            .await
+            .unwrap();
+
+        let counts = count_selections(&selector, 2000).await;
+        assert!(counts[1] > counts[2] * 2, "{:?}", counts);
+    }
+
+    #[tokio::test]
+    async fn test_new_proxy_gets_neutral_weight() {
+        let selector = WeightedRandomSelector::new();
+        selector
+            .refresh(vec![
+                create_test_proxy(1, 100, 90, 100),
+                create_test_proxy(2, 100, 10, 100),
+                create_test_proxy(3, 0, 0, 0),
+            ])
+            .await
+            .unwrap();
+
+        let counts = count_selections(&selector, 3000).await;
+        assert!(counts[3] > counts[2], "{:?}", counts);
+    }
+}
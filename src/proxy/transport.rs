@@ -2,17 +2,61 @@
 //!
 //! Handles establishing connections through upstream proxies.
 
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use hyper::Uri;
-use tokio::io::{AsyncRead, AsyncWrite};
+use hyper::header::HOST;
+use hyper::http::uri::Authority;
+use hyper::{HeaderMap, Uri};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio_socks::tcp::{Socks4Stream, Socks5Stream};
+use tokio_socks::tcp::Socks5Stream;
 use tracing::{debug, instrument};
 
-use crate::config::EgressProxyConfig;
+use crate::config::{EgressProxyConfig, Limits};
 use crate::error::{Result, RotaError};
 use crate::models::Proxy;
 use crate::proxy::egress;
+use crate::proxy::warm_pool::WarmPool;
+
+/// Connect timeouts for each upstream proxy protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectTimeouts {
+    pub http: Duration,
+    pub https: Duration,
+    /// Used for both SOCKS4 and SOCKS4a
+    pub socks4: Duration,
+    pub socks5: Duration,
+}
+
+impl ConnectTimeouts {
+    /// Use the same timeout for every protocol
+    pub fn uniform(timeout: Duration) -> Self {
+        Self {
+            http: timeout,
+            https: timeout,
+            socks4: timeout,
+            socks5: timeout,
+        }
+    }
+
+    /// Timeout for connecting through a proxy of `protocol`
+    pub fn for_protocol(&self, protocol: &str) -> Duration {
+        match protocol.to_lowercase().as_str() {
+            "https" => self.https,
+            "socks4" | "socks4a" => self.socks4,
+            "socks5" => self.socks5,
+            _ => self.http,
+        }
+    }
+}
+
+impl Default for ConnectTimeouts {
+    fn default() -> Self {
+        Self::uniform(Duration::from_secs(10))
+    }
+}
 
 /// Proxy transport handler
 ///
@@ -27,35 +71,110 @@ impl ProxyTransport {
         target_host: &str,
         target_port: u16,
         egress_proxy: Option<&EgressProxyConfig>,
+    ) -> Result<Box<dyn ProxyConnection>> {
+        Self::connect_limited(
+            proxy,
+            target_host,
+            target_port,
+            egress_proxy,
+            Limits::default().max_connect_reply_bytes,
+            None,
+        )
+        .await
+    }
+
+    /// Connect to a target, bounding an HTTP proxy's CONNECT reply to `max_connect_reply` bytes
+    /// and taking the proxy connection from `warm_pool` when one is given
+    async fn connect_limited(
+        proxy: &Proxy,
+        target_host: &str,
+        target_port: u16,
+        egress_proxy: Option<&EgressProxyConfig>,
+        max_connect_reply: usize,
+        warm_pool: Option<&WarmPool>,
     ) -> Result<Box<dyn ProxyConnection>> {
         let protocol = proxy.protocol.to_lowercase();
         match protocol.as_str() {
             "http" | "https" => {
-                Self::connect_http(proxy, target_host, target_port, egress_proxy).await
+                Self::connect_http(
+                    proxy,
+                    target_host,
+                    target_port,
+                    egress_proxy,
+                    max_connect_reply,
+                    warm_pool,
+                )
+                .await
+            }
+            "socks4" => {
+                Self::connect_socks4(proxy, target_host, target_port, egress_proxy, warm_pool).await
+            }
+            "socks4a" => {
+                Self::connect_socks4a(proxy, target_host, target_port, egress_proxy, warm_pool)
+                    .await
+            }
+            "socks5" => {
+                Self::connect_socks5(proxy, target_host, target_port, egress_proxy, warm_pool).await
             }
-            "socks4" => Self::connect_socks4(proxy, target_host, target_port, egress_proxy).await,
-            "socks4a" => Self::connect_socks4a(proxy, target_host, target_port, egress_proxy).await,
-            "socks5" => Self::connect_socks5(proxy, target_host, target_port, egress_proxy).await,
             _ => Err(RotaError::UnsupportedProtocol(protocol)),
         }
     }
 
+    /// Connect to a target through the proxy, bounded by the proxy protocol's timeout
+    /// and the configured CONNECT reply size, reusing a pre-dialed connection from
+    /// `warm_pool` when one is ready
+    pub async fn connect_with_timeouts(
+        proxy: &Proxy,
+        target_host: &str,
+        target_port: u16,
+        egress_proxy: Option<&EgressProxyConfig>,
+        timeouts: &ConnectTimeouts,
+        limits: &Limits,
+        warm_pool: &WarmPool,
+    ) -> Result<Box<dyn ProxyConnection>> {
+        tokio::time::timeout(
+            timeouts.for_protocol(&proxy.protocol),
+            Self::connect_limited(
+                proxy,
+                target_host,
+                target_port,
+                egress_proxy,
+                limits.max_connect_reply_bytes,
+                Some(warm_pool),
+            ),
+        )
+        .await
+        .map_err(|_| RotaError::Timeout)?
+    }
+
+    /// Open the TCP connection to the proxy itself, from the warm pool when one is given
+    async fn dial(
+        proxy: &Proxy,
+        egress_proxy: Option<&EgressProxyConfig>,
+        warm_pool: Option<&WarmPool>,
+    ) -> Result<TcpStream> {
+        match warm_pool {
+            Some(pool) => pool.dial(&proxy.address, egress_proxy).await,
+            None => egress::connect_to_addr(egress_proxy, &proxy.address).await,
+        }
+    }
+
     /// Connect through HTTP CONNECT method
     async fn connect_http(
         proxy: &Proxy,
         target_host: &str,
         target_port: u16,
         egress_proxy: Option<&EgressProxyConfig>,
+        max_connect_reply: usize,
+        warm_pool: Option<&WarmPool>,
     ) -> Result<Box<dyn ProxyConnection>> {
         debug!("Connecting to HTTP proxy at {}", proxy.address);
 
-        let stream = egress::connect_to_addr(egress_proxy, &proxy.address).await?;
+        let stream = Self::dial(proxy, egress_proxy, warm_pool).await?;
 
         // Send CONNECT request
         let connect_request = Self::build_connect_request(proxy, target_host, target_port);
 
-        use tokio::io::{AsyncReadExt, AsyncWriteExt};
-
         let mut stream = stream;
         stream
             .write_all(connect_request.as_bytes())
@@ -64,13 +183,35 @@ impl ProxyTransport {
                 RotaError::ProxyConnectionFailed(format!("Failed to send CONNECT: {}", e))
             })?;
 
-        // Read response
-        let mut response = vec![0u8; 1024];
-        let n = stream.read(&mut response).await.map_err(|e| {
-            RotaError::ProxyConnectionFailed(format!("Failed to read CONNECT response: {}", e))
-        })?;
+        // Read the response headers. Anything the proxy sends after the terminator
+        // already belongs to the tunnel and must be handed to the caller.
+        let mut response = Vec::with_capacity(1024);
+        let header_end = loop {
+            let header_end = find_header_end(&response);
+            let header_len = header_end.unwrap_or(response.len());
+            if Limits::exceeds(max_connect_reply, header_len) {
+                return Err(RotaError::ResponseTooLarge(format!(
+                    "CONNECT reply headers exceed {} bytes",
+                    max_connect_reply
+                )));
+            }
+            if let Some(pos) = header_end {
+                break pos;
+            }
+
+            let mut chunk = [0u8; 1024];
+            let n = stream.read(&mut chunk).await.map_err(|e| {
+                RotaError::ProxyConnectionFailed(format!("Failed to read CONNECT response: {}", e))
+            })?;
+            if n == 0 {
+                return Err(RotaError::ProxyConnectionFailed(
+                    "Proxy closed connection during CONNECT".to_string(),
+                ));
+            }
+            response.extend_from_slice(&chunk[..n]);
+        };
 
-        let response_str = String::from_utf8_lossy(&response[..n]);
+        let response_str = String::from_utf8_lossy(&response[..header_end]);
         if !response_str.starts_with("HTTP/1.1 200") && !response_str.starts_with("HTTP/1.0 200") {
             return Err(RotaError::ProxyConnectionFailed(format!(
                 "CONNECT failed: {}",
@@ -79,15 +220,22 @@ impl ProxyTransport {
         }
 
         debug!("HTTP CONNECT tunnel established");
-        Ok(Box::new(TcpConnection(stream)))
+        let leftover = response.split_off(header_end);
+        if leftover.is_empty() {
+            Ok(Box::new(TcpConnection(stream)))
+        } else {
+            debug!(
+                bytes = leftover.len(),
+                "Preserving data sent after CONNECT reply"
+            );
+            Ok(Box::new(PrefixedConnection::new(leftover, stream)))
+        }
     }
 
     /// Build HTTP CONNECT request
     fn build_connect_request(proxy: &Proxy, target_host: &str, target_port: u16) -> String {
-        let mut request = format!(
-            "CONNECT {}:{} HTTP/1.1\r\nHost: {}:{}\r\n",
-            target_host, target_port, target_host, target_port
-        );
+        let authority = egress::format_connect_authority(target_host, target_port);
+        let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", authority, authority);
 
         // Add proxy authentication if credentials are provided
         if let (Some(username), Some(password)) = (&proxy.username, &proxy.password) {
@@ -106,30 +254,29 @@ impl ProxyTransport {
         target_host: &str,
         target_port: u16,
         egress_proxy: Option<&EgressProxyConfig>,
+        warm_pool: Option<&WarmPool>,
     ) -> Result<Box<dyn ProxyConnection>> {
         debug!("Connecting to SOCKS4 proxy at {}", proxy.address);
 
         // SOCKS4 requires IP address, not hostname
-        let target_ip: std::net::Ipv4Addr = target_host.parse().map_err(|_| {
+        let target_ip: Ipv4Addr = target_host.parse().map_err(|_| {
             RotaError::ProxyConnectionFailed(
                 "SOCKS4 requires IP address, not hostname. Use SOCKS4a or SOCKS5 for DNS resolution"
                     .to_string(),
             )
         })?;
 
-        let target_addr = std::net::SocketAddrV4::new(target_ip, target_port);
+        let mut stream = Self::dial(proxy, egress_proxy, warm_pool).await?;
 
-        let socket = egress::connect_to_addr(egress_proxy, &proxy.address).await?;
-
-        let stream = if let Some(user_id) = proxy.username.as_deref() {
-            Socks4Stream::connect_with_userid_and_socket(socket, target_addr, user_id).await
-        } else {
-            Socks4Stream::connect_with_socket(socket, target_addr).await
-        }
-        .map_err(|e| RotaError::ProxyConnectionFailed(format!("SOCKS4 connect failed: {}", e)))?;
+        let request = build_socks4_request(
+            Socks4Target::Ip(target_ip),
+            target_port,
+            proxy.username.as_deref(),
+        );
+        socks4_handshake(&mut stream, &request, "SOCKS4").await?;
 
         debug!("SOCKS4 connection established");
-        Ok(Box::new(TcpConnection(stream.into_inner())))
+        Ok(Box::new(TcpConnection(stream)))
     }
 
     /// Connect through SOCKS4a proxy (supports hostname)
@@ -138,27 +285,22 @@ impl ProxyTransport {
         target_host: &str,
         target_port: u16,
         egress_proxy: Option<&EgressProxyConfig>,
+        warm_pool: Option<&WarmPool>,
     ) -> Result<Box<dyn ProxyConnection>> {
         debug!("Connecting to SOCKS4a proxy at {}", proxy.address);
 
-        let socket = egress::connect_to_addr(egress_proxy, &proxy.address).await?;
+        let mut stream = Self::dial(proxy, egress_proxy, warm_pool).await?;
 
         let target_host = normalize_socks_host(target_host);
-
-        let stream = if let Some(user_id) = proxy.username.as_deref() {
-            Socks4Stream::connect_with_userid_and_socket(
-                socket,
-                (target_host, target_port),
-                user_id,
-            )
-            .await
-        } else {
-            Socks4Stream::connect_with_socket(socket, (target_host, target_port)).await
-        }
-        .map_err(|e| RotaError::ProxyConnectionFailed(format!("SOCKS4a connect failed: {}", e)))?;
+        let target = match target_host.parse::<Ipv4Addr>() {
+            Ok(ip) => Socks4Target::Ip(ip),
+            Err(_) => Socks4Target::Host(target_host),
+        };
+        let request = build_socks4_request(target, target_port, proxy.username.as_deref());
+        socks4_handshake(&mut stream, &request, "SOCKS4a").await?;
 
         debug!("SOCKS4a connection established");
-        Ok(Box::new(TcpConnection(stream.into_inner())))
+        Ok(Box::new(TcpConnection(stream)))
     }
 
     /// Connect through SOCKS5 proxy
@@ -167,10 +309,11 @@ impl ProxyTransport {
         target_host: &str,
         target_port: u16,
         egress_proxy: Option<&EgressProxyConfig>,
+        warm_pool: Option<&WarmPool>,
     ) -> Result<Box<dyn ProxyConnection>> {
         debug!("Connecting to SOCKS5 proxy at {}", proxy.address);
 
-        let socket = egress::connect_to_addr(egress_proxy, &proxy.address).await?;
+        let socket = Self::dial(proxy, egress_proxy, warm_pool).await?;
 
         let target_host = normalize_socks_host(target_host);
 
@@ -206,23 +349,58 @@ impl ProxyTransport {
         Ok((host, port))
     }
 
+    /// Target of a proxied HTTP request, in absolute form or origin form
+    ///
+    /// Absolute-form URIs (`GET http://host/path`) carry the target themselves. Some
+    /// clients send origin form (`GET /path`) to a proxy, naming the target only in the
+    /// `Host` header, which is used when the URI has no host.
+    pub fn parse_request_target(uri: &Uri, headers: &HeaderMap) -> Result<(String, u16)> {
+        if uri.host().is_some() {
+            return Self::parse_target(uri);
+        }
+
+        let authority = headers
+            .get(HOST)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<Authority>().ok())
+            .ok_or_else(|| {
+                RotaError::InvalidRequest("Missing host in URI and Host header".to_string())
+            })?;
+        Ok((
+            authority.host().to_string(),
+            authority.port_u16().unwrap_or(80),
+        ))
+    }
+
     /// Parse host and port from authority (for CONNECT requests)
+    ///
+    /// IPv6 hosts come back without their brackets, as `egress::parse_host_port` returns
+    /// them. A missing port defaults to 443, since CONNECT is typically for HTTPS.
     pub fn parse_authority(authority: &str) -> Result<(String, u16)> {
-        // Bracketed IPv6 without an explicit port: "[::1]"
-        if authority.starts_with('[') && authority.ends_with(']') {
-            // Default to port 443 for CONNECT (typically HTTPS)
-            return Ok((authority.to_string(), 443));
-        }
+        let invalid = || RotaError::InvalidRequest(format!("Invalid authority '{}'", authority));
 
-        if let Some((host, port_str)) = authority.rsplit_once(':') {
-            let port = port_str
-                .parse::<u16>()
-                .map_err(|_| RotaError::InvalidRequest("Invalid port".to_string()))?;
-            Ok((host.to_string(), port))
+        let (host, port_str) = if let Some(rest) = authority.strip_prefix('[') {
+            let (host, after) = rest.split_once(']').ok_or_else(invalid)?;
+            host.parse::<Ipv6Addr>().map_err(|_| invalid())?;
+            let port_str = match after {
+                "" => None,
+                after => Some(after.strip_prefix(':').ok_or_else(invalid)?),
+            };
+            (host, port_str)
         } else {
-            // Default to port 443 for CONNECT (typically HTTPS)
-            Ok((authority.to_string(), 443))
-        }
+            match authority.rsplit_once(':') {
+                Some((host, port_str)) => (host, Some(port_str)),
+                None => (authority, None),
+            }
+        };
+
+        let port = match port_str {
+            Some(port_str) => port_str
+                .parse::<u16>()
+                .map_err(|_| RotaError::InvalidRequest("Invalid port".to_string()))?,
+            None => 443,
+        };
+        Ok((host.to_string(), port))
     }
 }
 
@@ -268,6 +446,134 @@ impl AsyncWrite for TcpConnection {
 
 impl ProxyConnection for TcpConnection {}
 
+/// Connection that replays bytes read ahead of the tunnel before reading from the stream
+struct PrefixedConnection {
+    prefix: Vec<u8>,
+    pos: usize,
+    stream: TcpStream,
+}
+
+impl PrefixedConnection {
+    fn new(prefix: Vec<u8>, stream: TcpStream) -> Self {
+        Self {
+            prefix,
+            pos: 0,
+            stream,
+        }
+    }
+}
+
+impl AsyncRead for PrefixedConnection {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        if self.pos < self.prefix.len() {
+            let n = (self.prefix.len() - self.pos).min(buf.remaining());
+            let start = self.pos;
+            buf.put_slice(&self.prefix[start..start + n]);
+            self.pos += n;
+            return std::task::Poll::Ready(Ok(()));
+        }
+        std::pin::Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PrefixedConnection {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        std::pin::Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+impl ProxyConnection for PrefixedConnection {}
+
+/// Offset just past the `\r\n\r\n` that ends an HTTP header block
+fn find_header_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|pos| pos + 4)
+}
+
+/// Destination of a SOCKS4 CONNECT request
+#[derive(Clone, Copy)]
+enum Socks4Target<'a> {
+    Ip(Ipv4Addr),
+    /// Resolved by the proxy (SOCKS4a)
+    Host(&'a str),
+}
+
+/// Build a SOCKS4/SOCKS4a CONNECT request
+///
+/// SOCKS4a signals a hostname with the `0.0.0.1` sentinel IP and appends the hostname
+/// after the user ID.
+fn build_socks4_request(target: Socks4Target<'_>, port: u16, user_id: Option<&str>) -> Vec<u8> {
+    let ip = match target {
+        Socks4Target::Ip(ip) => ip,
+        Socks4Target::Host(_) => Ipv4Addr::new(0, 0, 0, 1),
+    };
+
+    let mut request = vec![0x04, 0x01];
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&ip.octets());
+    request.extend_from_slice(user_id.unwrap_or_default().as_bytes());
+    request.push(0x00);
+    if let Socks4Target::Host(host) = target {
+        request.extend_from_slice(host.as_bytes());
+        request.push(0x00);
+    }
+    request
+}
+
+/// Send a SOCKS4 request and check the 8-byte reply
+async fn socks4_handshake(stream: &mut TcpStream, request: &[u8], label: &str) -> Result<()> {
+    stream.write_all(request).await.map_err(|e| {
+        RotaError::ProxyConnectionFailed(format!("{} connect failed: {}", label, e))
+    })?;
+
+    let mut reply = [0u8; 8];
+    stream.read_exact(&mut reply).await.map_err(|e| {
+        RotaError::ProxyConnectionFailed(format!("{} connect failed: {}", label, e))
+    })?;
+
+    if reply[0] != 0x00 {
+        return Err(RotaError::ProxyConnectionFailed(format!(
+            "{} connect failed: invalid reply version 0x{:02X}",
+            label, reply[0]
+        )));
+    }
+
+    let reason = match reply[1] {
+        0x5A => return Ok(()),
+        0x5B => "request rejected or failed",
+        0x5C => "proxy could not reach identd on the client",
+        0x5D => "identd user ID mismatch",
+        _ => "unknown reply code",
+    };
+    Err(RotaError::ProxyConnectionFailed(format!(
+        "{} connect failed: {} (0x{:02X})",
+        label, reason, reply[1]
+    )))
+}
+
 fn normalize_socks_host(host: &str) -> &str {
     host.strip_prefix('[')
         .and_then(|h| h.strip_suffix(']'))
@@ -306,6 +612,57 @@ mod tests {
         assert!(matches!(err, RotaError::InvalidRequest(_)));
     }
 
+    #[test]
+    fn test_parse_request_target_origin_form_uses_host_header() {
+        let uri: Uri = "/relative/path?q=1".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, "example.com".parse().unwrap());
+        assert_eq!(
+            ProxyTransport::parse_request_target(&uri, &headers).unwrap(),
+            ("example.com".to_string(), 80)
+        );
+
+        headers.insert(HOST, "[::1]:8080".parse().unwrap());
+        assert_eq!(
+            ProxyTransport::parse_request_target(&uri, &headers).unwrap(),
+            ("[::1]".to_string(), 8080)
+        );
+
+        // Absolute form wins over the Host header
+        let uri: Uri = "https://other.example/path".parse().unwrap();
+        assert_eq!(
+            ProxyTransport::parse_request_target(&uri, &headers).unwrap(),
+            ("other.example".to_string(), 443)
+        );
+    }
+
+    #[test]
+    fn test_parse_request_target_without_any_host() {
+        let uri: Uri = "/relative/path".parse().unwrap();
+        let err = ProxyTransport::parse_request_target(&uri, &HeaderMap::new()).unwrap_err();
+        assert!(matches!(err, RotaError::InvalidRequest(_)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, "bad host".parse().unwrap());
+        assert!(ProxyTransport::parse_request_target(&uri, &headers).is_err());
+    }
+
+    #[test]
+    fn test_connect_request_only_carries_upstream_credentials() {
+        let mut proxy = test_proxy("127.0.0.1:8080".to_string(), "http");
+        let request = ProxyTransport::build_connect_request(&proxy, "example.com", 443);
+        assert_eq!(
+            request,
+            "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n"
+        );
+
+        proxy.username = Some("user".to_string());
+        proxy.password = Some("pass".to_string());
+        let request = ProxyTransport::build_connect_request(&proxy, "example.com", 443);
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+        assert!(!request.contains("\r\nAuthorization:"));
+    }
+
     #[test]
     fn test_parse_authority_host_and_port() {
         let (host, port) = ProxyTransport::parse_authority("example.com:8080").unwrap();
@@ -320,15 +677,33 @@ mod tests {
         assert_eq!(port, 443);
 
         let (host, port) = ProxyTransport::parse_authority("[::1]").unwrap();
-        assert_eq!(host, "[::1]");
+        assert_eq!(host, "::1");
         assert_eq!(port, 443);
     }
 
     #[test]
     fn test_parse_authority_ipv6() {
         let (host, port) = ProxyTransport::parse_authority("[::1]:8443").unwrap();
-        assert_eq!(host, "[::1]");
+        assert_eq!(host, "::1");
         assert_eq!(port, 8443);
+
+        let (host, port) = ProxyTransport::parse_authority("[2001:db8::1]:443").unwrap();
+        assert_eq!(host, "2001:db8::1");
+        assert_eq!(port, 443);
+
+        let (host, port) = ProxyTransport::parse_authority("example.com:443").unwrap();
+        assert_eq!(host, "example.com");
+        assert_eq!(port, 443);
+
+        // Brackets come back when the authority is sent upstream
+        let proxy = test_proxy("127.0.0.1:8080".to_string(), "http");
+        let request = ProxyTransport::build_connect_request(&proxy, "2001:db8::1", 443);
+        assert!(request.starts_with("CONNECT [2001:db8::1]:443 HTTP/1.1\r\n"));
+
+        for bad in ["[::1", "[::1]8443", "[not-an-ip]:443"] {
+            let err = ProxyTransport::parse_authority(bad).unwrap_err();
+            assert!(matches!(err, RotaError::InvalidRequest(_)), "{}", bad);
+        }
     }
 
     #[test]
@@ -336,4 +711,405 @@ mod tests {
         let err = ProxyTransport::parse_authority("example.com:not-a-number").unwrap_err();
         assert!(matches!(err, RotaError::InvalidRequest(_)));
     }
+
+    fn test_proxy(address: String, protocol: &str) -> Proxy {
+        Proxy {
+            id: 1,
+            address,
+            protocol: protocol.to_string(),
+            username: None,
+            password: None,
+            status: "active".to_string(),
+            enabled: true,
+            country: None,
+            asn: None,
+            tags: Vec::new(),
+            requests: 0,
+            successful_requests: 0,
+            failed_requests: 0,
+            avg_response_time: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_check: None,
+            verified_at: None,
+            health_check_url: None,
+            expected_response_contains: None,
+            last_error: None,
+            auto_delete_after_failed_seconds: None,
+            invalid_since: None,
+            failure_reasons: serde_json::Value::Array(Vec::new()),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    async fn connect_through_fake_http_proxy(replies: Vec<&'static [u8]>) -> Vec<u8> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let upstream = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while find_header_end(&request).is_none() {
+                let mut chunk = [0u8; 256];
+                let n = socket.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            for reply in replies {
+                socket.write_all(reply).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        });
+
+        let proxy = test_proxy(addr.to_string(), "http");
+
+        let mut conn = ProxyTransport::connect(&proxy, "example.com", 443, None)
+            .await
+            .unwrap();
+        let mut received = Vec::new();
+        conn.read_to_end(&mut received).await.unwrap();
+
+        upstream.await.unwrap();
+        received
+    }
+
+    #[tokio::test]
+    async fn test_connect_http_preserves_bytes_after_reply() {
+        let received = connect_through_fake_http_proxy(vec![
+            b"HTTP/1.1 200 Connection Established\r\n\r\n\x16\x03\x01",
+            b"\x00\x05hello",
+        ])
+        .await;
+
+        assert_eq!(received, b"\x16\x03\x01\x00\x05hello");
+    }
+
+    #[tokio::test]
+    async fn test_connect_http_reply_split_across_reads() {
+        let received = connect_through_fake_http_proxy(vec![
+            b"HTTP/1.1 200 Connection Established\r\n",
+            b"Proxy-Agent: test\r\n\r\ntunnel",
+        ])
+        .await;
+
+        assert_eq!(received, b"tunnel");
+    }
+
+    #[tokio::test]
+    async fn test_connect_http_reply_over_limit_is_rejected() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _upstream = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut chunk = [0u8; 256];
+            let _ = socket.read(&mut chunk).await;
+            let padding = "x".repeat(512);
+            let _ = socket
+                .write_all(
+                    format!("HTTP/1.1 200 Connection Established\r\nX-Pad: {padding}\r\n\r\n")
+                        .as_bytes(),
+                )
+                .await;
+        });
+
+        let limits = Limits {
+            max_connect_reply_bytes: 256,
+            ..Limits::default()
+        };
+        let result = ProxyTransport::connect_with_timeouts(
+            &test_proxy(addr.to_string(), "http"),
+            "example.com",
+            443,
+            None,
+            &ConnectTimeouts::default(),
+            &limits,
+            &WarmPool::disabled(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(RotaError::ResponseTooLarge(_))));
+    }
+
+    async fn read_nul_terminated(stream: &mut TcpStream) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            let mut byte = [0u8; 1];
+            stream.read_exact(&mut byte).await.unwrap();
+            if byte[0] == 0x00 {
+                return out;
+            }
+            out.push(byte[0]);
+        }
+    }
+
+    /// Minimal SOCKS4 proxy: records the request, replies with `reply_code` and then
+    /// echoes tunnel bytes back on success
+    async fn spawn_socks4_proxy(
+        reply_code: u8,
+    ) -> (
+        std::net::SocketAddr,
+        tokio::task::JoinHandle<(Vec<u8>, Option<Vec<u8>>)>,
+    ) {
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = tokio::spawn(async move {
+            let (mut client, _) = listener.accept().await.unwrap();
+
+            // VN, CD, DSTPORT, DSTIP
+            let mut head = [0u8; 8];
+            client.read_exact(&mut head).await.unwrap();
+            assert_eq!(head[0], 0x04);
+            assert_eq!(head[1], 0x01);
+
+            let user_id = read_nul_terminated(&mut client).await;
+
+            // SOCKS4a: 0.0.0.x with x != 0 means a hostname follows
+            let hostname = if head[4..7] == [0, 0, 0] && head[7] != 0 {
+                Some(read_nul_terminated(&mut client).await)
+            } else {
+                None
+            };
+
+            client
+                .write_all(&[0x00, reply_code, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            if reply_code == 0x5A {
+                let mut buf = [0u8; 4];
+                client.read_exact(&mut buf).await.unwrap();
+                client.write_all(&buf).await.unwrap();
+            }
+
+            let mut request = head.to_vec();
+            request.extend_from_slice(&user_id);
+            (request, hostname)
+        });
+
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn test_connect_socks4_sends_ip_and_userid() {
+        let (addr, proxy_task) = spawn_socks4_proxy(0x5A).await;
+        let mut proxy = test_proxy(addr.to_string(), "socks4");
+        proxy.username = Some("alice".to_string());
+
+        let mut conn = ProxyTransport::connect(&proxy, "10.1.2.3", 8443, None)
+            .await
+            .unwrap();
+        conn.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        let (request, hostname) = proxy_task.await.unwrap();
+        let mut expected = vec![0x04, 0x01];
+        expected.extend_from_slice(&8443u16.to_be_bytes());
+        expected.extend_from_slice(&[10, 1, 2, 3]);
+        expected.extend_from_slice(b"alice");
+        assert_eq!(request, expected);
+        assert!(hostname.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_connect_socks4_rejects_hostnames() {
+        let proxy = test_proxy("127.0.0.1:1".to_string(), "socks4");
+        let result = ProxyTransport::connect(&proxy, "example.com", 443, None).await;
+        assert!(matches!(result, Err(RotaError::ProxyConnectionFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connect_socks4a_sends_hostname_with_sentinel_ip() {
+        let (addr, proxy_task) = spawn_socks4_proxy(0x5A).await;
+        let proxy = test_proxy(addr.to_string(), "socks4a");
+
+        let mut conn = ProxyTransport::connect(&proxy, "example.com", 443, None)
+            .await
+            .unwrap();
+        conn.write_all(b"pong").await.unwrap();
+        let mut buf = [0u8; 4];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+
+        let (request, hostname) = proxy_task.await.unwrap();
+        assert_eq!(request[2..4], 443u16.to_be_bytes());
+        assert_eq!(request[4..8], [0, 0, 0, 1]);
+        assert_eq!(hostname.as_deref(), Some(&b"example.com"[..]));
+    }
+
+    #[tokio::test]
+    async fn test_connect_socks4_rejection_reports_reply_code() {
+        for (code, protocol) in [(0x5B, "socks4"), (0x5C, "socks4a"), (0x5D, "socks4a")] {
+            let (addr, proxy_task) = spawn_socks4_proxy(code).await;
+            let proxy = test_proxy(addr.to_string(), protocol);
+
+            let result = ProxyTransport::connect(&proxy, "127.0.0.1", 80, None).await;
+            match result {
+                Err(RotaError::ProxyConnectionFailed(msg)) => {
+                    assert!(msg.contains(&format!("0x{:02X}", code)), "{}", msg)
+                }
+                other => panic!("expected rejection, got {:?}", other.err()),
+            }
+            proxy_task.await.unwrap();
+        }
+    }
+
+    /// HTTP CONNECT relay: reports the requested authority, dials it and pipes bytes
+    async fn spawn_connect_relay() -> (std::net::SocketAddr, tokio::task::JoinHandle<String>) {
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = tokio::spawn(async move {
+            let (mut client, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while find_header_end(&request).is_none() {
+                let mut chunk = [0u8; 256];
+                let n = client.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&request).to_string();
+            let authority = request.split_whitespace().nth(1).unwrap().to_string();
+
+            let mut server = TcpStream::connect(&authority).await.unwrap();
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await
+                .unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+            authority
+        });
+
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn test_connect_dials_upstream_proxy_through_egress() {
+        use crate::config::EgressProxyProtocol;
+        use tokio::net::TcpListener;
+
+        let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target_addr = target.local_addr().unwrap();
+        let echo = tokio::spawn(async move {
+            let (mut socket, _) = target.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            socket.read_exact(&mut buf).await.unwrap();
+            socket.write_all(&buf).await.unwrap();
+        });
+
+        let (upstream_addr, upstream) = spawn_connect_relay().await;
+        let (egress_addr, egress) = spawn_connect_relay().await;
+        let egress_config = EgressProxyConfig {
+            protocol: EgressProxyProtocol::Http,
+            host: egress_addr.ip().to_string(),
+            port: egress_addr.port(),
+            username: None,
+            password: None,
+        };
+
+        let proxy = test_proxy(upstream_addr.to_string(), "http");
+        let mut conn = ProxyTransport::connect(
+            &proxy,
+            "127.0.0.1",
+            target_addr.port(),
+            Some(&egress_config),
+        )
+        .await
+        .unwrap();
+
+        conn.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        tokio::time::timeout(Duration::from_secs(5), conn.read_exact(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf, b"ping");
+        drop(conn);
+
+        echo.await.unwrap();
+        assert_eq!(upstream.await.unwrap(), target_addr.to_string());
+        assert_eq!(egress.await.unwrap(), upstream_addr.to_string());
+    }
+
+    #[test]
+    fn test_find_header_end() {
+        assert_eq!(find_header_end(b"HTTP/1.1 200 OK\r\n\r\nrest"), Some(19));
+        assert_eq!(find_header_end(b"HTTP/1.1 200 OK\r\n"), None);
+    }
+
+    #[test]
+    fn test_connect_timeouts_for_protocol() {
+        let timeouts = ConnectTimeouts {
+            http: Duration::from_secs(1),
+            https: Duration::from_secs(2),
+            socks4: Duration::from_secs(3),
+            socks5: Duration::from_secs(4),
+        };
+
+        assert_eq!(timeouts.for_protocol("http"), Duration::from_secs(1));
+        assert_eq!(timeouts.for_protocol("HTTPS"), Duration::from_secs(2));
+        assert_eq!(timeouts.for_protocol("socks4"), Duration::from_secs(3));
+        assert_eq!(timeouts.for_protocol("socks4a"), Duration::from_secs(3));
+        assert_eq!(timeouts.for_protocol("socks5"), Duration::from_secs(4));
+        assert_eq!(timeouts.for_protocol("unknown"), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_timeouts_uses_protocol_timeout() {
+        use tokio::net::TcpListener;
+
+        // Accepts connections but never answers a handshake.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _blackhole = tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let slow = Duration::from_secs(30);
+        let fast = Duration::from_millis(100);
+
+        for protocol in ["http", "https", "socks4", "socks5"] {
+            let mut timeouts = ConnectTimeouts::uniform(slow);
+            match protocol {
+                "http" => timeouts.http = fast,
+                "https" => timeouts.https = fast,
+                "socks4" => timeouts.socks4 = fast,
+                _ => timeouts.socks5 = fast,
+            }
+
+            let proxy = test_proxy(addr.to_string(), protocol);
+            let started = std::time::Instant::now();
+            let result = ProxyTransport::connect_with_timeouts(
+                &proxy,
+                "127.0.0.1",
+                443,
+                None,
+                &timeouts,
+                &Limits::default(),
+                &WarmPool::disabled(),
+            )
+            .await;
+
+            assert!(matches!(result, Err(RotaError::Timeout)), "{}", protocol);
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "{} used the wrong timeout",
+                protocol
+            );
+        }
+    }
 }
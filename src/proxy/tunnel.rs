@@ -3,11 +3,13 @@
 //! Handles bidirectional data transfer between client and target server.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::sync::watch;
 use tracing::{debug, instrument};
 
 use crate::config::EgressProxyConfig;
@@ -142,6 +144,54 @@ impl Drop for TunnelGuard {
     }
 }
 
+/// Counts open tunnels so shutdown can wait for them to finish
+#[derive(Clone)]
+pub struct TunnelTracker {
+    open: Arc<watch::Sender<usize>>,
+}
+
+impl TunnelTracker {
+    pub fn new() -> Self {
+        Self {
+            open: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    /// Count a tunnel as open until the returned guard is dropped
+    pub fn track(&self) -> TrackedTunnel {
+        self.open.send_modify(|open| *open += 1);
+        TrackedTunnel(self.open.clone())
+    }
+
+    pub fn open(&self) -> usize {
+        *self.open.borrow()
+    }
+
+    /// Wait up to `grace` for every tunnel to close; returns whether they all did
+    pub async fn wait_closed(&self, grace: Duration) -> bool {
+        let mut open = self.open.subscribe();
+        let closed = tokio::time::timeout(grace, open.wait_for(|open| *open == 0))
+            .await
+            .is_ok();
+        closed
+    }
+}
+
+impl Default for TunnelTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps a tunnel counted by its [`TunnelTracker`] while alive
+pub struct TrackedTunnel(Arc<watch::Sender<usize>>);
+
+impl Drop for TrackedTunnel {
+    fn drop(&mut self) {
+        self.0.send_modify(|open| *open -= 1);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
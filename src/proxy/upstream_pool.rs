@@ -0,0 +1,165 @@
+//! Keep-alive pool of HTTP/1.1 connections to upstream proxies
+//!
+//! Plain HTTP requests are forwarded to the upstream proxy over HTTP/1.1. When the proxy
+//! keeps the connection alive, the sender handle is parked here once the previous response
+//! has been read, and the next request through the same proxy reuses it instead of dialing
+//! and handshaking again.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body_util::Full;
+use hyper::client::conn::http1::SendRequest;
+
+use crate::config::EgressProxyConfig;
+use crate::models::Proxy;
+
+/// Idle connections older than this are dropped rather than reused
+const IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+pub type UpstreamSender = SendRequest<Full<Bytes>>;
+
+struct IdleSender {
+    /// Proxy address the connection was dialed to; the proxy may since have been edited
+    address: String,
+    /// Egress proxy (host, port) the connection goes through
+    egress: Option<(String, u16)>,
+    sender: UpstreamSender,
+    idle_since: Instant,
+}
+
+/// Idle HTTP/1.1 senders to upstream proxies, keyed by proxy id
+#[derive(Clone)]
+pub struct UpstreamPool {
+    idle: Arc<DashMap<i32, Vec<IdleSender>>>,
+    max_idle_per_proxy: usize,
+}
+
+impl UpstreamPool {
+    /// Create a pool keeping up to `max_idle_per_proxy` idle connections per proxy (0 = off)
+    pub fn new(max_idle_per_proxy: usize) -> Self {
+        Self {
+            idle: Arc::new(DashMap::new()),
+            max_idle_per_proxy,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_idle_per_proxy > 0
+    }
+
+    /// Take the most recently used idle connection to `proxy` through `egress_proxy`
+    pub fn checkout(
+        &self,
+        proxy: &Proxy,
+        egress_proxy: Option<&EgressProxyConfig>,
+    ) -> Option<UpstreamSender> {
+        let mut idle = self.idle.get_mut(&proxy.id)?;
+        idle.retain(|s| !s.sender.is_closed() && s.idle_since.elapsed() < IDLE_TIMEOUT);
+
+        let egress = egress_key(egress_proxy);
+        let pos = idle
+            .iter()
+            .rposition(|s| s.address == proxy.address && s.egress == egress)?;
+        Some(idle.remove(pos).sender)
+    }
+
+    /// Return a sender whose request has been answered
+    ///
+    /// It is parked once the response body has been read and the connection is idle;
+    /// connections the upstream closes are dropped instead.
+    pub fn checkin(
+        &self,
+        proxy: &Proxy,
+        egress_proxy: Option<&EgressProxyConfig>,
+        mut sender: UpstreamSender,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let pool = self.clone();
+        let proxy_id = proxy.id;
+        let address = proxy.address.clone();
+        let egress = egress_key(egress_proxy);
+        tokio::spawn(async move {
+            if sender.ready().await.is_err() {
+                return;
+            }
+            let mut idle = pool.idle.entry(proxy_id).or_default();
+            if idle.len() >= pool.max_idle_per_proxy {
+                idle.remove(0);
+            }
+            idle.push(IdleSender {
+                address,
+                egress,
+                sender,
+                idle_since: Instant::now(),
+            });
+        });
+    }
+
+    /// Number of idle connections held for a proxy
+    pub fn idle_count(&self, proxy_id: i32) -> usize {
+        self.idle.get(&proxy_id).map_or(0, |idle| idle.len())
+    }
+
+    /// Drop every idle connection, so later requests dial and handshake afresh
+    ///
+    /// Returns how many connections were dropped. Connections serving a request right
+    /// now are checked in again afterwards.
+    pub fn clear(&self) -> usize {
+        let mut dropped = 0;
+        self.idle.retain(|_, idle| {
+            dropped += idle.len();
+            false
+        });
+        dropped
+    }
+}
+
+fn egress_key(egress_proxy: Option<&EgressProxyConfig>) -> Option<(String, u16)> {
+    egress_proxy.map(|e| (e.host.clone(), e.port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hyper_util::rt::TokioIo;
+
+    async fn idle_sender(address: &str) -> IdleSender {
+        let (client, _server) = tokio::io::duplex(64);
+        let (sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(client))
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+        IdleSender {
+            address: address.to_string(),
+            egress: None,
+            sender,
+            idle_since: Instant::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clear_empties_the_pool() {
+        let pool = UpstreamPool::new(8);
+        pool.idle.entry(1).or_default().extend([
+            idle_sender("127.0.0.1:8081").await,
+            idle_sender("127.0.0.1:8081").await,
+        ]);
+        pool.idle
+            .entry(2)
+            .or_default()
+            .push(idle_sender("127.0.0.1:8082").await);
+
+        assert_eq!(pool.clear(), 3);
+        assert_eq!(pool.idle_count(1), 0);
+        assert_eq!(pool.idle_count(2), 0);
+        assert!(pool.idle.is_empty());
+        assert_eq!(pool.clear(), 0);
+    }
+}
@@ -0,0 +1,360 @@
+//! Warm pool of pre-dialed upstream proxy connections
+//!
+//! A CONNECT tunnel (or SOCKS session) owns its proxy connection until the tunnel closes,
+//! and HTTP proxies that accept a second CONNECT on the same connection are rare, so
+//! tunnels themselves can't be reused. What can be saved is the TCP (and egress) handshake
+//! to the proxy: for proxies that are used often, the pool keeps a few idle connections
+//! dialed ahead, so the next request only has to run the proxy protocol handshake.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::net::TcpStream;
+use tracing::debug;
+
+use crate::config::{EgressProxyConfig, WarmPoolConfig};
+use crate::error::Result;
+use crate::proxy::egress;
+
+/// Window in which uses are counted against the hot threshold
+const HOT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Proxy address and the egress proxy (host, port) it is dialed through
+type PoolKey = (String, Option<(String, u16)>);
+
+/// Hit/miss counters and idle connections held by the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WarmPoolStats {
+    /// Dials served from an idle pre-dialed connection
+    pub hits: u64,
+    /// Dials that had to connect on demand
+    pub misses: u64,
+    /// Idle connections currently held
+    pub idle: usize,
+}
+
+#[derive(Default)]
+struct Entry {
+    idle: VecDeque<(TcpStream, Instant)>,
+    /// Dial-ahead connections in flight
+    dialing: usize,
+    window_start: Option<Instant>,
+    uses: u32,
+}
+
+impl Entry {
+    /// Count a use and return whether the proxy is hot
+    fn record_use(&mut self, now: Instant, threshold: u32) -> bool {
+        match self.window_start {
+            Some(start) if now.duration_since(start) < HOT_WINDOW => self.uses += 1,
+            _ => {
+                self.window_start = Some(now);
+                self.uses = 1;
+            }
+        }
+        self.uses >= threshold
+    }
+
+    /// Take the oldest idle connection that is still open and not expired
+    fn take_fresh(&mut self, max_idle: Duration) -> Option<TcpStream> {
+        while let Some((stream, dialed_at)) = self.idle.pop_front() {
+            if dialed_at.elapsed() <= max_idle && is_open(&stream) {
+                return Some(stream);
+            }
+        }
+        None
+    }
+
+    /// Reserve dial-ahead slots to bring the entry up to `size`, returning how many to dial
+    fn reserve(&mut self, size: usize) -> usize {
+        let needed = size.saturating_sub(self.idle.len() + self.dialing);
+        self.dialing += needed;
+        needed
+    }
+
+    fn is_unused(&self, now: Instant) -> bool {
+        self.idle.is_empty()
+            && self.dialing == 0
+            && self
+                .window_start
+                .is_none_or(|start| now.duration_since(start) >= HOT_WINDOW)
+    }
+}
+
+/// Whether an idle connection is still usable: not closed by the proxy and with no
+/// unsolicited data waiting
+fn is_open(stream: &TcpStream) -> bool {
+    let mut buf = [0u8; 1];
+    matches!(stream.try_read(&mut buf), Err(e) if e.kind() == io::ErrorKind::WouldBlock)
+}
+
+struct Inner {
+    config: WarmPoolConfig,
+    entries: Mutex<HashMap<PoolKey, Entry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Pre-dialed idle TCP connections to hot upstream proxies
+#[derive(Clone)]
+pub struct WarmPool {
+    inner: Arc<Inner>,
+}
+
+impl WarmPool {
+    pub fn new(config: WarmPoolConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                entries: Mutex::new(HashMap::new()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Create a pool that never keeps connections and always dials on demand
+    pub fn disabled() -> Self {
+        Self::new(WarmPoolConfig::default())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.config.size > 0
+    }
+
+    fn max_idle(&self) -> Duration {
+        Duration::from_secs(self.inner.config.max_idle_seconds)
+    }
+
+    /// Open a TCP connection to the proxy at `address`, preferring a pre-dialed one
+    ///
+    /// Once the proxy is hot, dials ahead in the background to keep `size` idle
+    /// connections ready for the next requests.
+    pub async fn dial(
+        &self,
+        address: &str,
+        egress_proxy: Option<&EgressProxyConfig>,
+    ) -> Result<TcpStream> {
+        if !self.is_enabled() {
+            return egress::connect_to_addr(egress_proxy, address).await;
+        }
+
+        let key: PoolKey = (
+            address.to_string(),
+            egress_proxy.map(|e| (e.host.clone(), e.port)),
+        );
+        let (warm, dial_ahead) = {
+            let mut entries = self.inner.entries.lock();
+            let entry = entries.entry(key.clone()).or_default();
+            let hot = entry.record_use(Instant::now(), self.inner.config.hot_threshold);
+            let warm = entry.take_fresh(self.max_idle());
+            let dial_ahead = if hot {
+                entry.reserve(self.inner.config.size)
+            } else {
+                0
+            };
+            (warm, dial_ahead)
+        };
+
+        for _ in 0..dial_ahead {
+            self.spawn_dial_ahead(key.clone(), egress_proxy.cloned());
+        }
+
+        match warm {
+            Some(stream) => {
+                self.inner.hits.fetch_add(1, Ordering::Relaxed);
+                debug!("Using pre-dialed connection to {}", address);
+                Ok(stream)
+            }
+            None => {
+                self.inner.misses.fetch_add(1, Ordering::Relaxed);
+                egress::connect_to_addr(egress_proxy, address).await
+            }
+        }
+    }
+
+    fn spawn_dial_ahead(&self, key: PoolKey, egress_proxy: Option<EgressProxyConfig>) {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            let result = egress::connect_to_addr(egress_proxy.as_ref(), &key.0).await;
+            let mut entries = pool.inner.entries.lock();
+            let entry = entries.entry(key.clone()).or_default();
+            entry.dialing = entry.dialing.saturating_sub(1);
+            match result {
+                Ok(stream) => entry.idle.push_back((stream, Instant::now())),
+                Err(e) => debug!("Dial-ahead to {} failed: {}", key.0, e),
+            }
+        });
+    }
+
+    /// Drop expired idle connections and forget proxies that have cooled down
+    pub fn prune(&self) {
+        let max_idle = self.max_idle();
+        let now = Instant::now();
+        let mut entries = self.inner.entries.lock();
+        for entry in entries.values_mut() {
+            entry
+                .idle
+                .retain(|(stream, dialed_at)| dialed_at.elapsed() <= max_idle && is_open(stream));
+        }
+        entries.retain(|_, entry| !entry.is_unused(now));
+    }
+
+    /// Drop every idle pre-dialed connection, so later dials connect afresh
+    ///
+    /// Returns how many connections were dropped. Proxies that are still hot dial ahead
+    /// again on their next use.
+    pub fn clear(&self) -> usize {
+        self.inner
+            .entries
+            .lock()
+            .values_mut()
+            .map(|entry| entry.idle.drain(..).count())
+            .sum()
+    }
+
+    pub fn stats(&self) -> WarmPoolStats {
+        WarmPoolStats {
+            hits: self.inner.hits.load(Ordering::Relaxed),
+            misses: self.inner.misses.load(Ordering::Relaxed),
+            idle: self
+                .inner
+                .entries
+                .lock()
+                .values()
+                .map(|entry| entry.idle.len())
+                .sum(),
+        }
+    }
+}
+
+impl Default for WarmPool {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::AtomicUsize;
+    use tokio::net::TcpListener;
+
+    /// Accept connections and count them; kept open unless `close` is set
+    async fn spawn_counting_proxy(close: bool) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                if !close {
+                    open.push(stream);
+                }
+            }
+        });
+        (address, accepted)
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("condition not reached");
+    }
+
+    fn pool(size: usize, hot_threshold: u32) -> WarmPool {
+        WarmPool::new(WarmPoolConfig {
+            size,
+            hot_threshold,
+            max_idle_seconds: 30,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_hot_proxy_is_dialed_ahead() {
+        let (address, accepted) = spawn_counting_proxy(false).await;
+        let pool = pool(2, 1);
+
+        let _first = pool.dial(&address, None).await.unwrap();
+        wait_until(|| pool.stats().idle == 2).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+        assert_eq!(pool.stats().misses, 1);
+
+        // The next dial takes a pre-dialed connection and the pool tops itself back up
+        let _second = pool.dial(&address, None).await.unwrap();
+        assert_eq!(pool.stats().hits, 1);
+        wait_until(|| pool.stats().idle == 2).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_cold_proxy_is_not_dialed_ahead() {
+        let (address, accepted) = spawn_counting_proxy(false).await;
+        let pool = pool(2, 3);
+
+        let _first = pool.dial(&address, None).await.unwrap();
+        let _second = pool.dial(&address, None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.stats().idle, 0);
+
+        // The third use within the window makes it hot
+        let _third = pool.dial(&address, None).await.unwrap();
+        wait_until(|| pool.stats().idle == 2).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_closed_idle_connections_are_not_handed_out() {
+        let (address, accepted) = spawn_counting_proxy(true).await;
+        let pool = pool(1, 1);
+
+        let _first = pool.dial(&address, None).await.unwrap();
+        wait_until(|| pool.stats().idle == 1).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let _second = pool.dial(&address, None).await.unwrap();
+        assert_eq!(pool.stats().hits, 0);
+        assert_eq!(pool.stats().misses, 2);
+    }
+
+    #[tokio::test]
+    async fn test_clear_drops_idle_connections() {
+        let (address, accepted) = spawn_counting_proxy(false).await;
+        let pool = pool(2, 1);
+
+        let _first = pool.dial(&address, None).await.unwrap();
+        wait_until(|| pool.stats().idle == 2).await;
+        assert_eq!(pool.clear(), 2);
+        assert_eq!(pool.stats().idle, 0);
+
+        // The next dial connects afresh
+        let _second = pool.dial(&address, None).await.unwrap();
+        assert_eq!(pool.stats().hits, 0);
+        wait_until(|| accepted.load(Ordering::SeqCst) >= 4).await;
+    }
+
+    #[tokio::test]
+    async fn test_disabled_pool_dials_on_demand() {
+        let (address, accepted) = spawn_counting_proxy(false).await;
+        let pool = WarmPool::disabled();
+
+        let _first = pool.dial(&address, None).await.unwrap();
+        let _second = pool.dial(&address, None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.stats(), WarmPoolStats::default());
+    }
+}
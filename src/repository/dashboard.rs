@@ -1,6 +1,8 @@
 use crate::database::timescale;
 use crate::error::Result;
-use crate::models::{ChartData, ChartDataPoint, ChartTimeRange, DashboardStats};
+use crate::models::{
+    AttemptBucket, ChartData, ChartDataPoint, ChartTimeRange, DashboardStats, RetryStats, SlaStats,
+};
 use sqlx::PgPool;
 
 /// Repository for dashboard statistics
@@ -97,7 +99,70 @@ impl DashboardRepository {
         Ok((0.0, 0.0, 0))
     }
 
-    /// Get request count chart data
+    /// Get the share of requests within SLA over a time range
+    ///
+    /// Requests recorded before SLA tagging existed are not counted. Rows are weighted by
+    /// `sample_weight` so sampled-out successes count too.
+    pub async fn get_sla_stats(&self, range: &ChartTimeRange) -> Result<SlaStats> {
+        let (total, within): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                COALESCE(ROUND(SUM(sample_weight)), 0)::bigint AS total,
+                COALESCE(ROUND(SUM(sample_weight) FILTER (WHERE within_sla)), 0)::bigint AS within
+            FROM proxy_requests
+            WHERE timestamp >= $1 AND timestamp <= $2 AND within_sla IS NOT NULL
+            "#,
+        )
+        .bind(range.start_time())
+        .bind(range.end_time())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(SlaStats::new(total, within))
+    }
+
+    /// Get how many attempts requests needed over a time range
+    ///
+    /// Successes are bucketed by the attempt they succeeded on; overall failures are the
+    /// records not attributed to a proxy. Rows recorded before attempts were tracked are
+    /// not counted; the rest are weighted by `sample_weight`.
+    pub async fn get_retry_stats(&self, range: &ChartTimeRange) -> Result<RetryStats> {
+        let buckets: Vec<(i32, i64)> = sqlx::query_as(
+            r#"
+            SELECT attempts, ROUND(SUM(sample_weight))::bigint AS requests
+            FROM proxy_requests
+            WHERE timestamp >= $1 AND timestamp <= $2 AND attempts IS NOT NULL AND success
+            GROUP BY attempts
+            ORDER BY attempts
+            "#,
+        )
+        .bind(range.start_time())
+        .bind(range.end_time())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let failed: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(ROUND(SUM(sample_weight)), 0)::bigint
+            FROM proxy_requests
+            WHERE timestamp >= $1 AND timestamp <= $2 AND attempts IS NOT NULL
+              AND NOT success AND proxy_id = 0
+            "#,
+        )
+        .bind(range.start_time())
+        .bind(range.end_time())
+        .fetch_one(&self.pool)
+        .await?;
+
+        let succeeded = buckets
+            .into_iter()
+            .map(|(attempts, requests)| AttemptBucket { attempts, requests })
+            .collect();
+
+        Ok(RetryStats::new(succeeded, failed))
+    }
+
+    /// Get request count chart data, weighting sampled successes
     pub async fn get_request_chart(&self, range: &ChartTimeRange) -> Result<ChartData> {
         let start = range.start_time();
         let end = range.end_time();
@@ -109,7 +174,7 @@ impl DashboardRepository {
                     r#"
                     SELECT
                         time_bucket(INTERVAL '{}', timestamp) AS bucket,
-                        COUNT(*)::float AS value
+                        SUM(sample_weight)::float AS value
                     FROM proxy_requests
                     WHERE timestamp >= $1 AND timestamp <= $2
                     GROUP BY bucket
@@ -130,7 +195,7 @@ impl DashboardRepository {
                     r#"
                     SELECT
                         to_timestamp(floor(extract(epoch from timestamp) / $3) * $3) AS bucket,
-                        COUNT(*)::float AS value
+                        SUM(sample_weight)::float AS value
                     FROM proxy_requests
                     WHERE timestamp >= $1 AND timestamp <= $2
                     GROUP BY 1
@@ -156,7 +221,7 @@ impl DashboardRepository {
         })
     }
 
-    /// Get success rate chart data
+    /// Get success rate chart data, weighting sampled successes
     pub async fn get_success_rate_chart(&self, range: &ChartTimeRange) -> Result<ChartData> {
         let start = range.start_time();
         let end = range.end_time();
@@ -169,8 +234,8 @@ impl DashboardRepository {
                     SELECT
                         time_bucket(INTERVAL '{}', timestamp) AS bucket,
                         COALESCE(
-                            (SUM(CASE WHEN success THEN 1 ELSE 0 END)::float /
-                             NULLIF(COUNT(*), 0)::float) * 100,
+                            (SUM(CASE WHEN success THEN sample_weight ELSE 0 END) /
+                             NULLIF(SUM(sample_weight), 0)) * 100,
                             0
                         ) AS value
                     FROM proxy_requests
@@ -194,8 +259,8 @@ impl DashboardRepository {
                     SELECT
                         to_timestamp(floor(extract(epoch from timestamp) / $3) * $3) AS bucket,
                         COALESCE(
-                            (SUM(CASE WHEN success THEN 1 ELSE 0 END)::float /
-                             NULLIF(COUNT(*), 0)::float) * 100,
+                            (SUM(CASE WHEN success THEN sample_weight ELSE 0 END) /
+                             NULLIF(SUM(sample_weight), 0)) * 100,
                             0
                         ) AS value
                     FROM proxy_requests
@@ -223,7 +288,7 @@ impl DashboardRepository {
         })
     }
 
-    /// Get response time chart data
+    /// Get response time chart data, weighting sampled successes
     pub async fn get_response_time_chart(&self, range: &ChartTimeRange) -> Result<ChartData> {
         let start = range.start_time();
         let end = range.end_time();
@@ -235,7 +300,10 @@ impl DashboardRepository {
                     r#"
                     SELECT
                         time_bucket(INTERVAL '{}', timestamp) AS bucket,
-                        COALESCE(AVG(response_time)::float, 0) AS value
+                        COALESCE(
+                            SUM(response_time * sample_weight) / NULLIF(SUM(sample_weight), 0),
+                            0
+                        ) AS value
                     FROM proxy_requests
                     WHERE timestamp >= $1 AND timestamp <= $2
                     GROUP BY bucket
@@ -256,7 +324,10 @@ impl DashboardRepository {
                     r#"
                     SELECT
                         to_timestamp(floor(extract(epoch from timestamp) / $3) * $3) AS bucket,
-                        COALESCE(AVG(response_time)::float, 0) AS value
+                        COALESCE(
+                            SUM(response_time * sample_weight) / NULLIF(SUM(sample_weight), 0),
+                            0
+                        ) AS value
                     FROM proxy_requests
                     WHERE timestamp >= $1 AND timestamp <= $2
                     GROUP BY 1
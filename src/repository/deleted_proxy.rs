@@ -31,7 +31,7 @@ impl DeletedProxyRepository {
             r#"
             SELECT id, address, protocol, username, password, status,
                    requests, successful_requests, failed_requests,
-                   avg_response_time, last_check, last_error,
+                   avg_response_time, bytes_sent, bytes_received, last_check, last_error,
                    auto_delete_after_failed_seconds, invalid_since, deleted_at, failure_reasons,
                    created_at, updated_at
             FROM deleted_proxies
@@ -55,7 +55,7 @@ impl DeletedProxyRepository {
             r#"
             SELECT id, address, protocol, username, password, status,
                    requests, successful_requests, failed_requests,
-                   avg_response_time, last_check, last_error,
+                   avg_response_time, bytes_sent, bytes_received, last_check, last_error,
                    auto_delete_after_failed_seconds, invalid_since, deleted_at, failure_reasons,
                    created_at, updated_at
             FROM deleted_proxies
@@ -95,7 +95,7 @@ impl DeletedProxyRepository {
             r#"
             SELECT id, address, protocol, username, password, status,
                    requests, successful_requests, failed_requests,
-                   avg_response_time, last_check, last_error,
+                   avg_response_time, bytes_sent, bytes_received, last_check, last_error,
                    auto_delete_after_failed_seconds, invalid_since, deleted_at, failure_reasons,
                    created_at, updated_at
             FROM deleted_proxies
@@ -115,20 +115,21 @@ impl DeletedProxyRepository {
             INSERT INTO proxies (
                 id, address, protocol, username, password, status,
                 requests, successful_requests, failed_requests, avg_response_time,
-                last_check, last_error,
+                bytes_sent, bytes_received, last_check, last_error,
                 auto_delete_after_failed_seconds, invalid_since, failure_reasons,
                 created_at, updated_at
             )
             VALUES (
                 $1, $2, $3, $4, $5, 'idle',
                 $6, $7, $8, $9,
-                $10, $11,
-                $12, NULL, '[]'::jsonb,
-                $13, NOW()
+                $10, $11, $12, $13,
+                $14, NULL, '[]'::jsonb,
+                $15, NOW()
             )
-            RETURNING id, address, protocol, username, password, status,
+            RETURNING id, address, protocol, username, password, status, enabled, country, asn, tags,
                       requests, successful_requests, failed_requests,
-                      avg_response_time, last_check, last_error,
+                      avg_response_time, last_check, verified_at, health_check_url,
+                      expected_response_contains, last_error, bytes_sent, bytes_received,
                       auto_delete_after_failed_seconds, invalid_since, failure_reasons,
                       created_at, updated_at
             "#,
@@ -142,6 +143,8 @@ impl DeletedProxyRepository {
         .bind(deleted.successful_requests)
         .bind(deleted.failed_requests)
         .bind(deleted.avg_response_time)
+        .bind(deleted.bytes_sent)
+        .bind(deleted.bytes_received)
         .bind(deleted.last_check)
         .bind(&deleted.last_error)
         .bind(deleted.auto_delete_after_failed_seconds)
@@ -126,14 +126,18 @@ impl LogRepository {
         Ok(logs)
     }
 
-    /// Record a proxy request
-    pub async fn record_request(&self, record: &RequestRecord) -> Result<()> {
+    /// Record a proxy request that stands for `sample_weight` requests
+    ///
+    /// The weight is 1 unless the request was kept from a sample, so aggregates summing
+    /// it estimate every request.
+    pub async fn record_request(&self, record: &RequestRecord, sample_weight: f64) -> Result<()> {
         sqlx::query(
             r#"
             INSERT INTO proxy_requests
             (proxy_id, proxy_address, requested_url, method, success,
-             response_time, status_code, error_message, timestamp)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             response_time, status_code, error_message, timestamp, within_sla, attempts,
+             gateway_latency, sample_weight)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             "#,
         )
         .bind(record.proxy_id)
@@ -145,6 +149,10 @@ impl LogRepository {
         .bind(record.status_code)
         .bind(&record.error_message)
         .bind(record.timestamp)
+        .bind(record.within_sla)
+        .bind(record.attempts)
+        .bind(record.gateway_latency)
+        .bind(sample_weight)
         .execute(&self.pool)
         .await?;
 
@@ -24,40 +24,7 @@ impl SettingsRepository {
                 .fetch_all(&self.pool)
                 .await?;
 
-        let mut settings = Settings::default();
-
-        for record in records {
-            match record.key.as_str() {
-                keys::AUTHENTICATION => {
-                    if let Ok(v) = serde_json::from_value(record.value) {
-                        settings.authentication = v;
-                    }
-                }
-                keys::ROTATION => {
-                    if let Ok(v) = serde_json::from_value(record.value) {
-                        settings.rotation = v;
-                    }
-                }
-                keys::RATE_LIMIT => {
-                    if let Ok(v) = serde_json::from_value(record.value) {
-                        settings.rate_limit = v;
-                    }
-                }
-                keys::HEALTHCHECK => {
-                    if let Ok(v) = serde_json::from_value(record.value) {
-                        settings.healthcheck = v;
-                    }
-                }
-                keys::LOG_RETENTION => {
-                    if let Ok(v) = serde_json::from_value(record.value) {
-                        settings.log_retention = v;
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        Ok(settings)
+        Ok(settings_from_records(records))
     }
 
     /// Get a specific setting by key
@@ -134,11 +101,55 @@ impl SettingsRepository {
         self.set(keys::HEALTHCHECK, &settings.healthcheck).await?;
         self.set(keys::LOG_RETENTION, &settings.log_retention)
             .await?;
+        self.set(keys::CORS, &settings.cors).await?;
+        self.set(keys::RESPONSES, &settings.responses).await?;
 
         info!("Updated all settings");
         Ok(())
     }
 
+    /// Merge a partial settings document over the stored settings
+    ///
+    /// Runs in one transaction with the settings table locked, so concurrent patches
+    /// touching different fields don't overwrite each other. Only the top-level
+    /// sections present in `patch` are written back, and nothing is written when the
+    /// merged settings fail validation.
+    pub async fn patch(&self, patch: &serde_json::Value) -> Result<Settings> {
+        let sections = patch_sections(patch)?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("LOCK TABLE settings IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        let records =
+            sqlx::query_as::<_, SettingsRecord>("SELECT key, value, updated_at FROM settings")
+                .fetch_all(&mut *tx)
+                .await?;
+        let merged = merge_settings(&settings_from_records(records), patch)?;
+
+        let merged_value = serde_json::to_value(&merged)
+            .map_err(|e| RotaError::Internal(format!("Failed to serialize setting: {}", e)))?;
+        for key in &sections {
+            sqlx::query(
+                r#"
+                INSERT INTO settings (key, value)
+                VALUES ($1, $2)
+                ON CONFLICT (key) DO UPDATE SET value = $2, updated_at = NOW()
+                "#,
+            )
+            .bind(key)
+            .bind(&merged_value[key.as_str()])
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        info!(sections = ?sections, "Patched settings");
+        Ok(merged)
+    }
+
     /// Reset all settings to defaults
     pub async fn reset(&self) -> Result<Settings> {
         let defaults = Settings::default();
@@ -148,3 +159,168 @@ impl SettingsRepository {
         Ok(defaults)
     }
 }
+
+fn settings_from_records(records: Vec<SettingsRecord>) -> Settings {
+    let mut settings = Settings::default();
+
+    for record in records {
+        match record.key.as_str() {
+            keys::AUTHENTICATION => {
+                if let Ok(v) = serde_json::from_value(record.value) {
+                    settings.authentication = v;
+                }
+            }
+            keys::ROTATION => {
+                if let Ok(v) = serde_json::from_value(record.value) {
+                    settings.rotation = v;
+                }
+            }
+            keys::RATE_LIMIT => {
+                if let Ok(v) = serde_json::from_value(record.value) {
+                    settings.rate_limit = v;
+                }
+            }
+            keys::HEALTHCHECK => {
+                if let Ok(v) = serde_json::from_value(record.value) {
+                    settings.healthcheck = v;
+                }
+            }
+            keys::LOG_RETENTION => {
+                if let Ok(v) = serde_json::from_value(record.value) {
+                    settings.log_retention = v;
+                }
+            }
+            keys::CORS => {
+                if let Ok(v) = serde_json::from_value(record.value) {
+                    settings.cors = v;
+                }
+            }
+            keys::RESPONSES => {
+                if let Ok(v) = serde_json::from_value(record.value) {
+                    settings.responses = v;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    settings
+}
+
+/// Top-level settings sections named by a patch, rejecting anything else
+fn patch_sections(patch: &serde_json::Value) -> Result<Vec<String>> {
+    let object = patch.as_object().ok_or_else(|| {
+        RotaError::InvalidRequest("Settings patch must be a JSON object".to_string())
+    })?;
+
+    object
+        .keys()
+        .map(|key| match key.as_str() {
+            keys::AUTHENTICATION
+            | keys::ROTATION
+            | keys::RATE_LIMIT
+            | keys::HEALTHCHECK
+            | keys::LOG_RETENTION
+            | keys::CORS
+            | keys::RESPONSES => Ok(key.clone()),
+            _ => Err(RotaError::InvalidRequest(format!(
+                "Unknown settings section: {}",
+                key
+            ))),
+        })
+        .collect()
+}
+
+/// Apply `patch` over `current`, recursing into objects and replacing everything else
+///
+/// The merged settings must pass the same [`Settings::validate`] checks as a full update.
+pub fn merge_settings(current: &Settings, patch: &serde_json::Value) -> Result<Settings> {
+    patch_sections(patch)?;
+
+    let mut value = serde_json::to_value(current)
+        .map_err(|e| RotaError::Internal(format!("Failed to serialize setting: {}", e)))?;
+    merge_json(&mut value, patch);
+
+    let settings: Settings = serde_json::from_value(value)
+        .map_err(|e| RotaError::InvalidRequest(format!("Invalid settings: {}", e)))?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+fn merge_json(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_settings_patches_single_field() {
+        let mut current = Settings::default();
+        current.rotation.method = "round_robin".to_string();
+        current.rate_limit.max_requests = 42;
+
+        let merged =
+            merge_settings(&current, &json!({ "rate_limit": { "enabled": true } })).unwrap();
+
+        assert!(merged.rate_limit.enabled);
+        assert_eq!(merged.rate_limit.max_requests, 42);
+        assert_eq!(merged.rate_limit.interval, current.rate_limit.interval);
+        assert_eq!(merged.rotation.method, "round_robin");
+        assert_eq!(merged.healthcheck.url, Settings::default().healthcheck.url);
+    }
+
+    #[test]
+    fn test_merge_settings_rejects_invalid_patches() {
+        let current = Settings::default();
+
+        assert!(matches!(
+            merge_settings(&current, &json!(["rotation"])),
+            Err(RotaError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            merge_settings(&current, &json!({ "unknown": {} })),
+            Err(RotaError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            merge_settings(&current, &json!({ "rate_limit": { "interval": "often" } })),
+            Err(RotaError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_settings_validates_merged_settings() {
+        let mut current = Settings::default();
+        current.healthcheck.url = "http://a.example/,http://b.example/".to_string();
+
+        // Fine on its own, but the stored settings only list two URLs
+        assert!(matches!(
+            merge_settings(&current, &json!({ "healthcheck": { "quorum": 3 } })),
+            Err(RotaError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            merge_settings(
+                &current,
+                &json!({ "healthcheck": { "headers": ["no colon"] } })
+            ),
+            Err(RotaError::InvalidRequest(_))
+        ));
+
+        let merged = merge_settings(&current, &json!({ "healthcheck": { "quorum": 2 } })).unwrap();
+        assert_eq!(merged.healthcheck.quorum, 2);
+    }
+}
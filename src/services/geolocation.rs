@@ -0,0 +1,264 @@
+//! Proxy geolocation service
+//!
+//! Looks up proxies in offline MaxMind databases and stores their country and
+//! ASN. The lookup uses the IP of the proxy's address, which is also the exit
+//! IP for most single-homed proxies. Only started when a database is configured.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use maxminddb::{PathElement, Reader};
+use tokio::sync::watch;
+use tokio::time::interval;
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::database::Database;
+use crate::error::{Result, RotaError};
+use crate::repository::ProxyRepository;
+
+/// Country and ASN for one IP
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeoInfo {
+    /// ISO 3166-1 alpha-2 country code
+    pub country: Option<String>,
+    pub asn: Option<i64>,
+}
+
+impl GeoInfo {
+    pub fn is_empty(&self) -> bool {
+        self.country.is_none() && self.asn.is_none()
+    }
+}
+
+/// Source of geolocation data for an IP
+pub trait GeoLookup: Send + Sync {
+    fn lookup(&self, ip: IpAddr) -> GeoInfo;
+}
+
+/// Lookup backed by one or more MaxMind (`.mmdb`) databases
+///
+/// Country and ASN are read from whichever database has them, so a
+/// GeoLite2-Country and a GeoLite2-ASN database can be combined.
+pub struct MaxMindLookup {
+    readers: Vec<Reader<Vec<u8>>>,
+}
+
+impl MaxMindLookup {
+    /// Open the databases at `paths`; returns `None` when no path is configured
+    pub fn open(paths: &[String]) -> Result<Option<Self>> {
+        if paths.is_empty() {
+            return Ok(None);
+        }
+
+        let readers = paths
+            .iter()
+            .map(|path| {
+                Reader::open_readfile(path).map_err(|e| {
+                    RotaError::InvalidConfig(format!(
+                        "Failed to open GeoIP database '{}': {}",
+                        path, e
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(Self { readers }))
+    }
+}
+
+impl GeoLookup for MaxMindLookup {
+    fn lookup(&self, ip: IpAddr) -> GeoInfo {
+        let mut info = GeoInfo::default();
+
+        for reader in &self.readers {
+            let result = match reader.lookup(ip) {
+                Ok(result) => result,
+                Err(e) => {
+                    debug!(%ip, "GeoIP lookup failed: {}", e);
+                    continue;
+                }
+            };
+
+            if info.country.is_none() {
+                info.country = result
+                    .decode_path::<String>(&[
+                        PathElement::Key("country"),
+                        PathElement::Key("iso_code"),
+                    ])
+                    .ok()
+                    .flatten();
+            }
+            if info.asn.is_none() {
+                info.asn = result
+                    .decode_path::<u32>(&[PathElement::Key("autonomous_system_number")])
+                    .ok()
+                    .flatten()
+                    .map(i64::from);
+            }
+        }
+
+        info
+    }
+}
+
+/// Geolocate a proxy by the IP of its `host:port` address
+///
+/// Hostnames are resolved and the first address is used.
+pub async fn locate(lookup: &dyn GeoLookup, address: &str) -> Option<GeoInfo> {
+    let ip = match address.parse::<std::net::SocketAddr>() {
+        Ok(addr) => addr.ip(),
+        Err(_) => tokio::net::lookup_host(address).await.ok()?.next()?.ip(),
+    };
+
+    Some(lookup.lookup(ip))
+}
+
+/// Geolocation service configuration
+#[derive(Clone)]
+pub struct GeolocationConfig {
+    /// How often to look for proxies without geolocation data
+    pub check_interval: Duration,
+    /// Max number of proxies to look up per scan
+    pub batch_limit: i64,
+}
+
+impl Default for GeolocationConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(300),
+            batch_limit: 500,
+        }
+    }
+}
+
+/// Proxy geolocation service
+pub struct GeolocationService {
+    db: Database,
+    lookup: Arc<dyn GeoLookup>,
+    config: GeolocationConfig,
+}
+
+impl GeolocationService {
+    pub fn new(db: Database, lookup: Arc<dyn GeoLookup>, config: GeolocationConfig) -> Self {
+        Self { db, lookup, config }
+    }
+
+    /// Run the geolocation service
+    #[instrument(skip(self, shutdown))]
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        info!(
+            "Starting proxy geolocation service (interval: {}s)",
+            self.config.check_interval.as_secs()
+        );
+
+        let mut ticker = interval(self.config.check_interval);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Err(e) = self.enrich().await {
+                        error!("Proxy geolocation scan failed: {}", e);
+                    }
+                }
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        info!("Proxy geolocation service shutting down");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn enrich(&self) -> Result<()> {
+        let repo = ProxyRepository::new(self.db.pool().clone());
+        let mut located = 0usize;
+
+        for (id, address) in repo
+            .get_missing_geolocation(self.config.batch_limit)
+            .await?
+        {
+            // Unresolvable addresses and lookup misses are stored empty so they don't
+            // hold up the rest of the batch on every scan
+            let info = locate(self.lookup.as_ref(), &address)
+                .await
+                .unwrap_or_else(|| {
+                    warn!(id, %address, "Could not resolve proxy address for geolocation");
+                    GeoInfo::default()
+                });
+
+            repo.set_geolocation(id, info.country.as_deref(), info.asn)
+                .await?;
+            if !info.is_empty() {
+                located += 1;
+            }
+        }
+
+        if located > 0 {
+            info!(count = located, "Stored proxy geolocation");
+        }
+
+        Ok(())
+    }
+}
+
+/// Handle for managing the geolocation service
+pub struct GeolocationHandle {
+    shutdown_tx: watch::Sender<bool>,
+}
+
+impl GeolocationHandle {
+    pub fn new() -> (Self, watch::Receiver<bool>) {
+        let (tx, rx) = watch::channel(false);
+        (Self { shutdown_tx: tx }, rx)
+    }
+
+    pub fn shutdown(&self) {
+        let _ = self.shutdown_tx.send(true);
+    }
+}
+
+impl Default for GeolocationHandle {
+    fn default() -> Self {
+        Self::new().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticLookup;
+
+    impl GeoLookup for StaticLookup {
+        fn lookup(&self, ip: IpAddr) -> GeoInfo {
+            match ip.to_string().as_str() {
+                "203.0.113.7" => GeoInfo {
+                    country: Some("NL".to_string()),
+                    asn: Some(64500),
+                },
+                _ => GeoInfo::default(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_locate_uses_proxy_ip() {
+        let info = locate(&StaticLookup, "203.0.113.7:8080").await.unwrap();
+        assert_eq!(info.country.as_deref(), Some("NL"));
+        assert_eq!(info.asn, Some(64500));
+
+        let info = locate(&StaticLookup, "198.51.100.1:1080").await.unwrap();
+        assert!(info.is_empty());
+    }
+
+    #[test]
+    fn test_maxmind_lookup_disabled_without_paths() {
+        assert!(MaxMindLookup::open(&[]).unwrap().is_none());
+        assert!(matches!(
+            MaxMindLookup::open(&["/nonexistent/GeoLite2-Country.mmdb".to_string()]),
+            Err(RotaError::InvalidConfig(_))
+        ));
+    }
+}
@@ -1,7 +1,11 @@
 //! Background services
 
+pub mod control;
+pub mod geolocation;
 pub mod log_cleanup;
 pub mod proxy_auto_delete;
 
+pub use control::{PauseFlag, ServiceControls};
+pub use geolocation::{GeolocationConfig, GeolocationHandle, GeolocationService, MaxMindLookup};
 pub use log_cleanup::{LogCleanupConfig, LogCleanupHandle, LogCleanupService};
 pub use proxy_auto_delete::{ProxyAutoDeleteConfig, ProxyAutoDeleteHandle, ProxyAutoDeleteService};
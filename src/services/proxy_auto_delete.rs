@@ -15,6 +15,7 @@ use crate::error::Result;
 use crate::models::Settings;
 use crate::proxy::rotation::{DynamicProxySelector, ProxySelector};
 use crate::repository::ProxyRepository;
+use crate::services::PauseFlag;
 
 /// Proxy auto-delete service configuration
 #[derive(Clone)]
@@ -39,6 +40,7 @@ pub struct ProxyAutoDeleteService {
     db: Database,
     selector: Arc<DynamicProxySelector>,
     config: ProxyAutoDeleteConfig,
+    paused: PauseFlag,
 }
 
 impl ProxyAutoDeleteService {
@@ -51,9 +53,16 @@ impl ProxyAutoDeleteService {
             db,
             selector,
             config,
+            paused: PauseFlag::new("auto_delete"),
         }
     }
 
+    /// Skip scans while `paused` is set
+    pub fn with_pause(mut self, paused: PauseFlag) -> Self {
+        self.paused = paused;
+        self
+    }
+
     /// Run the proxy auto-delete service
     #[instrument(skip(self, shutdown, settings_rx))]
     pub async fn run(
@@ -80,7 +89,7 @@ impl ProxyAutoDeleteService {
             tokio::select! {
                 _ = ticker.tick() => {
                     let settings = settings_rx.borrow().clone();
-                    if let Err(e) = self.scan_and_archive(&settings).await {
+                    if let Some(Err(e)) = self.paused.run(self.scan_and_archive(&settings)).await {
                         error!("Proxy auto-delete scan failed: {}", e);
                     }
                 }
@@ -114,7 +123,8 @@ impl ProxyAutoDeleteService {
 
             // Refresh selector after changes.
             let proxies = if settings.rotation.remove_unhealthy {
-                repo.get_all_usable().await?
+                repo.get_all_usable(&settings.rotation.usable_status_filter())
+                    .await?
             } else {
                 repo.get_all().await?
             };
@@ -139,12 +149,22 @@ impl ProxyAutoDeleteService {
 /// Handle for managing the proxy auto-delete service
 pub struct ProxyAutoDeleteHandle {
     shutdown_tx: watch::Sender<bool>,
+    paused: PauseFlag,
 }
 
 impl ProxyAutoDeleteHandle {
     pub fn new() -> (Self, watch::Receiver<bool>) {
         let (tx, rx) = watch::channel(false);
-        (Self { shutdown_tx: tx }, rx)
+        let handle = Self {
+            shutdown_tx: tx,
+            paused: PauseFlag::new("auto_delete"),
+        };
+        (handle, rx)
+    }
+
+    /// Pause state to hand to the service and the API
+    pub fn pause_flag(&self) -> PauseFlag {
+        self.paused.clone()
     }
 
     pub fn shutdown(&self) {